@@ -0,0 +1,67 @@
+//! Compile-only check that the macros and the derive work without `std`.
+//!
+//! Built in CI with `--target thumbv7em-none-eabi`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use rust_code_obfuscator::{
+    obfuscate_bytes, obfuscate_calls, obfuscate_dummy_branch, obfuscate_include_str, obfuscate_num,
+    obfuscate_str, obfuscate_string, with_obfuscated, ObfBytes, ObfStr, Obfuscate,
+};
+
+#[derive(Obfuscate)]
+pub struct DeviceConfig {
+    pub serial: String,
+    pub enabled: bool,
+    pub threshold: f32,
+    pub retries: u8,
+}
+
+pub fn banner() -> ObfStr {
+    obfuscate_string!("firmware v1")
+}
+
+pub fn padded_token() -> &'static str {
+    obfuscate_str!("tok_123", pad = 32)
+}
+
+pub fn public_key() -> ObfBytes {
+    obfuscate_bytes!(b"\x04\x9a\x31\x00")
+}
+
+pub fn manifest() -> ObfStr {
+    obfuscate_include_str!("Cargo.toml")
+}
+
+pub fn calibration() -> (f32, u32, char) {
+    (
+        obfuscate_num!(0.875f32),
+        obfuscate_num!(4096u32),
+        obfuscate_num!('x'),
+    )
+}
+
+pub fn guarded_retries() -> u8 {
+    obfuscate_dummy_branch!();
+    obfuscate_num!(3u8)
+}
+
+fn scaled(value: u32, factor: u32) -> u32 {
+    value.wrapping_mul(factor)
+}
+
+#[obfuscate_calls(scaled)]
+pub fn scaled_threshold(value: u32) -> u32 {
+    scaled(value, 3)
+}
+
+pub fn device_config() -> DeviceConfig {
+    ObfuscatedDeviceConfig::new_clear("SN-0001", true, 0.5, 3).get_clear()
+}
+
+pub fn pin_len() -> usize {
+    with_obfuscated!("0000", |pin: &str| pin.len())
+}
@@ -39,4 +39,8 @@ pub struct Cli {
     /// Show unified diff; optional context lines (default 3)
     #[arg(long)]
     pub diff: Option<Option<usize>>,
+
+    /// Seed for reproducible output (defaults to OBFUSCATOR_SEED)
+    #[arg(long)]
+    pub seed: Option<String>,
 }
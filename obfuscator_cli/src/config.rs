@@ -1,11 +1,29 @@
+use std::fmt;
+
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize)]
+#[derive(Deserialize)]
 pub struct ObfuscateConfig {
     pub obfuscation: ObfuscationSection,
     pub identifiers: Option<IdentifiersSection>,
     pub include: Option<IncludeSection>,
     pub logging_macros: Option<LoggingMacrosSection>,
+    /// Makes generated names reproducible; overridden by `--seed` / `OBFUSCATOR_SEED`.
+    pub seed: Option<String>,
+}
+
+/// Every key share, literal key and rename suffix derives from `seed`, so it
+/// is redacted here to keep it out of logs.
+impl fmt::Debug for ObfuscateConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObfuscateConfig")
+            .field("obfuscation", &self.obfuscation)
+            .field("identifiers", &self.identifiers)
+            .field("include", &self.include)
+            .field("logging_macros", &self.logging_macros)
+            .field("seed", &self.seed.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -16,6 +34,8 @@ pub struct ObfuscationSection {
     pub control_flow: bool,
     pub control_flow_files: Option<Vec<String>>,
     pub dummy_branches: Option<bool>,
+    pub flatten_control_flow: Option<bool>,
+    pub indirect_calls: Option<Vec<String>>,
     pub obfuscate_logging: Option<bool>,
     pub skip_files: Option<Vec<String>>,
     pub skip_attributes: Option<bool>,
@@ -39,3 +59,20 @@ pub struct LoggingMacrosSection {
     pub enabled: Option<Vec<String>>,
     pub ignore_messages: Option<Vec<String>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_redacts_the_seed() {
+        let config: ObfuscateConfig = toml::from_str(
+            "seed = \"hunter2-seed\"\n[obfuscation]\nstrings = true\ncontrol_flow = false\n",
+        )
+        .unwrap();
+
+        let printed = format!("{config:?}");
+        assert!(!printed.contains("hunter2-seed"));
+        assert!(printed.contains("seed: Some(\"<redacted>\")"));
+    }
+}
@@ -179,6 +179,8 @@ mod tests {
                 control_flow: false,
                 control_flow_files: None,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files,
                 skip_attributes: None,
@@ -186,6 +188,7 @@ mod tests {
             identifiers: None,
             include,
             logging_macros: None,
+            seed: None,
         }
     }
 
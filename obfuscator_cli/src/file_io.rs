@@ -58,6 +58,7 @@ fn ensure_rust_file(file: &Path) -> Result<(), ObfuscatorError> {
 }
 
 #[cfg(test)]
+#[allow(clippy::no_effect, clippy::unused_unit)]
 mod tests {
     use super::*;
     use std::fs::File;
@@ -11,6 +11,7 @@ use clap::Parser;
 use cli::Cli;
 use config::ObfuscateConfig;
 use file_filter::filter_rust_files;
+use rust_code_obfuscator_core::utils::obfuscation_seed;
 use similar::TextDiff;
 use std::{fs, path::Path};
 
@@ -41,7 +42,10 @@ fn main() -> Result<()> {
 
     println!("Loading config from: {}", config_path.display());
     let config_str = fs::read_to_string(&config_path)?;
-    let config: ObfuscateConfig = toml::from_str(&config_str)?;
+    let mut config: ObfuscateConfig = toml::from_str(&config_str)?;
+    if let Some(seed) = args.seed.clone().or_else(obfuscation_seed) {
+        config.seed = Some(seed);
+    }
     dbg!(&config);
 
     if args.as_project {
@@ -140,6 +144,8 @@ ignore_strings = ["DEBUG", "LOG"]
 control_flow = true
 control_flow_files = ["**/*.rs"]
 dummy_branches = false
+flatten_control_flow = false
+indirect_calls = []
 obfuscate_logging = true
 skip_files = ["src/main.rs"]
 skip_attributes = true
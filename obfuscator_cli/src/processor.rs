@@ -2,10 +2,9 @@ use crate::config::ObfuscateConfig;
 use anyhow::Result;
 use globset::{Glob, GlobSetBuilder};
 use quote::{quote, quote_spanned};
-use rust_code_obfuscator_core::utils::generate_obf_suffix;
+use rust_code_obfuscator_core::utils::{generate_obf_suffix, seeded_obf_suffix};
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use syn::{
     parse::Parser, parse_file, punctuated::Punctuated, visit_mut::VisitMut, Expr, ExprForLoop,
@@ -39,10 +38,20 @@ pub fn process_file(
             .and_then(|id| id.preserve.clone())
             .unwrap_or_default(),
         rename_strategy: rename_strategy(config),
+        rename_seed: config.seed.clone(),
         obfuscate_strings: config.obfuscation.strings,
         obfuscate_flow,
         obfuscate_dummy_branches: obfuscate_flow
             && config.obfuscation.dummy_branches.unwrap_or(false),
+        flatten_control_flow: obfuscate_flow
+            && config.obfuscation.flatten_control_flow.unwrap_or(false),
+        indirect_calls: config
+            .obfuscation
+            .indirect_calls
+            .iter()
+            .flatten()
+            .cloned()
+            .collect(),
         obfuscate_logging: config.obfuscation.obfuscate_logging.unwrap_or(false),
         logging_macros: logging_macro_set(config),
         ignore_logging_messages: config
@@ -144,9 +153,12 @@ struct ObfuscationTransformer {
     rename_identifiers: bool,
     preserve_idents: Vec<String>,
     rename_strategy: RenameStrategy,
+    rename_seed: Option<String>,
     obfuscate_strings: bool,
     obfuscate_flow: bool,
     obfuscate_dummy_branches: bool,
+    flatten_control_flow: bool,
+    indirect_calls: HashSet<String>,
     obfuscate_logging: bool,
     logging_macros: HashSet<String>,
     ignore_logging_messages: Vec<String>,
@@ -393,6 +405,14 @@ impl VisitMut for ObfuscationTransformer {
 
         syn::visit_mut::visit_signature_mut(self, &mut func.sig);
         self.visit_block_mut(&mut func.block);
+        self.mark_for_flattening(&mut func.attrs, &func.sig);
+        self.mark_indirect_calls(&mut func.attrs, &func.sig, &func.block);
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, func: &mut syn::ImplItemFn) {
+        syn::visit_mut::visit_impl_item_fn_mut(self, func);
+        self.mark_for_flattening(&mut func.attrs, &func.sig);
+        self.mark_indirect_calls(&mut func.attrs, &func.sig, &func.block);
     }
 
     fn visit_pat_ident_mut(&mut self, pat: &mut PatIdent) {
@@ -460,6 +480,67 @@ impl ObfuscationTransformer {
         stmts
     }
 
+    /// Marks the fn with `#[rust_code_obfuscator::obfuscate(flatten)]` when
+    /// `flatten_control_flow` is set. The flattener lives in the proc-macro
+    /// crate, which the CLI does not link, so the body is flattened when the
+    /// processed crate compiles; that crate needs `rust_code_obfuscator` as a
+    /// dependency. The path is spelled out so fns in inline modules see it.
+    fn mark_for_flattening(&mut self, attrs: &mut Vec<syn::Attribute>, sig: &syn::Signature) {
+        let already_marked = attrs.iter().any(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "obfuscate")
+        });
+        if !self.flatten_control_flow || sig.constness.is_some() || already_marked {
+            return;
+        }
+
+        attrs.push(syn::parse_quote! { #[rust_code_obfuscator::obfuscate(flatten)] });
+    }
+
+    /// Adds `#[rust_code_obfuscator::obfuscate_calls(...)]` naming the
+    /// `indirect_calls` functions the body calls by bare name, under the names
+    /// they have after renaming. The path is spelled out so fns in inline
+    /// modules see it.
+    fn mark_indirect_calls(
+        &mut self,
+        attrs: &mut Vec<syn::Attribute>,
+        sig: &syn::Signature,
+        block: &syn::Block,
+    ) {
+        let already_marked = attrs.iter().any(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "obfuscate_calls")
+        });
+        if self.indirect_calls.is_empty() || sig.constness.is_some() || already_marked {
+            return;
+        }
+
+        let mut wanted = self.indirect_calls.clone();
+        wanted.extend(
+            self.indirect_calls
+                .iter()
+                .filter_map(|name| self.renamed_idents.get(name))
+                .map(Ident::to_string),
+        );
+        let mut calls = CalledTargets {
+            wanted: &wanted,
+            found: Vec::new(),
+        };
+        syn::visit::Visit::visit_block(&mut calls, block);
+        if calls.found.is_empty() {
+            return;
+        }
+
+        let targets = calls.found;
+        attrs.push(syn::parse_quote! {
+            #[rust_code_obfuscator::obfuscate_calls(#(#targets),*)]
+        });
+    }
+
     fn obfuscate_logging_macro(&self, node: &mut syn::Macro) -> bool {
         let parser = Punctuated::<Expr, Token![,]>::parse_terminated;
         let Ok(args) = parser.parse2(node.tokens.clone()) else {
@@ -531,7 +612,9 @@ impl ObfuscationTransformer {
 
     fn unique_obfuscated_name(&mut self, original: &str, kind: RenameKind) -> String {
         for attempt in 0..u32::MAX {
-            let candidate = self.rename_strategy.name_for(original, kind, attempt);
+            let candidate =
+                self.rename_strategy
+                    .name_for(original, kind, attempt, self.rename_seed.as_deref());
             if candidate != original
                 && !self.preserve_idents.contains(&candidate)
                 && self.generated_idents.insert(candidate.clone())
@@ -558,30 +641,42 @@ enum RenameStrategy {
 }
 
 impl RenameStrategy {
-    fn name_for(self, original: &str, kind: RenameKind, attempt: u32) -> String {
+    fn name_for(
+        self,
+        original: &str,
+        kind: RenameKind,
+        attempt: u32,
+        seed: Option<&str>,
+    ) -> String {
         match self {
-            RenameStrategy::Suffix => suffix_name(original, kind, attempt),
-            RenameStrategy::Hash => format!("_r{}", stable_base36(original, kind, attempt)),
-            RenameStrategy::Confuse => confuse_name(original, kind, attempt),
+            RenameStrategy::Suffix => suffix_name(original, kind, attempt, seed),
+            RenameStrategy::Hash => {
+                format!("_r{}", stable_base36(original, kind, attempt, seed))
+            }
+            RenameStrategy::Confuse => confuse_name(original, kind, attempt, seed),
         }
     }
 }
 
-fn suffix_name(original: &str, kind: RenameKind, attempt: u32) -> String {
+fn suffix_name(original: &str, kind: RenameKind, attempt: u32, seed: Option<&str>) -> String {
     let separator = match kind {
         RenameKind::Function => "_obf_",
         RenameKind::Binding => "_x",
     };
+    let suffix = match seed {
+        Some(seed) => seeded_obf_suffix(seed, &rename_context(original, kind, attempt)),
+        None => generate_obf_suffix(),
+    };
 
     if attempt == 0 {
-        format!("{original}{separator}{}", generate_obf_suffix())
+        format!("{original}{separator}{suffix}")
     } else {
-        format!("{original}{separator}{}_{}", generate_obf_suffix(), attempt)
+        format!("{original}{separator}{suffix}_{attempt}")
     }
 }
 
-fn confuse_name(original: &str, kind: RenameKind, attempt: u32) -> String {
-    let digest = stable_base36(original, kind, attempt);
+fn confuse_name(original: &str, kind: RenameKind, attempt: u32, seed: Option<&str>) -> String {
+    let digest = stable_base36(original, kind, attempt, seed);
     let mut chars = digest.chars();
     let first = match chars.next().unwrap_or('a') {
         ch if ch.is_ascii_alphabetic() => ch,
@@ -590,15 +685,37 @@ fn confuse_name(original: &str, kind: RenameKind, attempt: u32) -> String {
     format!("_{first}{}", chars.collect::<String>())
 }
 
-fn stable_base36(original: &str, kind: RenameKind, attempt: u32) -> String {
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    original.hash(&mut hasher);
-    attempt.hash(&mut hasher);
-    match kind {
-        RenameKind::Function => "fn".hash(&mut hasher),
-        RenameKind::Binding => "binding".hash(&mut hasher),
+/// FNV-1a over the seed and rename context. Unlike `DefaultHasher`, the output
+/// is fixed across Rust releases, so renames are reproducible.
+fn stable_base36(original: &str, kind: RenameKind, attempt: u32, seed: Option<&str>) -> String {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let seed = seed.unwrap_or_default();
+    let mut hash = FNV_OFFSET;
+    for byte in (seed.len() as u64)
+        .to_le_bytes()
+        .iter()
+        .chain(seed.as_bytes())
+        .chain(&rename_context(original, kind, attempt))
+    {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
     }
-    to_base36(hasher.finish())
+    to_base36(hash)
+}
+
+fn rename_context(original: &str, kind: RenameKind, attempt: u32) -> Vec<u8> {
+    let kind: &[u8] = match kind {
+        RenameKind::Function => b"fn",
+        RenameKind::Binding => b"binding",
+    };
+    let mut context = Vec::with_capacity(original.len() + kind.len() + 12);
+    context.extend_from_slice(&(original.len() as u32).to_le_bytes());
+    context.extend_from_slice(original.as_bytes());
+    context.extend_from_slice(&attempt.to_le_bytes());
+    context.extend_from_slice(kind);
+    context
 }
 
 fn to_base36(mut value: u64) -> String {
@@ -822,6 +939,32 @@ fn collect_idents_from_pat(pat: &Pat) -> Option<Ident> {
     }
 }
 
+/// Collects the distinct functions from `wanted` that a fn body calls by
+/// path. Nested items are left to their own visit.
+struct CalledTargets<'a> {
+    wanted: &'a HashSet<String>,
+    found: Vec<Ident>,
+}
+
+impl<'ast> syn::visit::Visit<'ast> for CalledTargets<'_> {
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        // Only unqualified calls: the attribute names bare functions, which
+        // select calls spelled the same way.
+        if let Expr::Path(path) = &*node.func {
+            let segments = &path.path.segments;
+            if path.qself.is_none() && path.path.leading_colon.is_none() && segments.len() == 1 {
+                let ident = &segments[0].ident;
+                if self.wanted.contains(&ident.to_string()) && !self.found.contains(ident) {
+                    self.found.push(ident.clone());
+                }
+            }
+        }
+        syn::visit::visit_expr_call(self, node);
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
 fn use_tree_contains_ident(tree: &syn::UseTree, target: &str) -> bool {
     match tree {
         syn::UseTree::Path(path) => use_tree_contains_ident(&path.tree, target),
@@ -836,6 +979,12 @@ fn use_tree_contains_ident(tree: &syn::UseTree, target: &str) -> bool {
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::enum_variant_names,
+    clippy::needless_return,
+    clippy::partialeq_to_none,
+    clippy::redundant_field_names
+)]
 mod tests {
     use super::*;
     use crate::config::ObfuscationSection;
@@ -878,6 +1027,8 @@ mod tests {
                 control_flow: flow,
                 control_flow_files: None,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files: skip_files,
                 skip_attributes: skip_attributes,
@@ -885,6 +1036,7 @@ mod tests {
             identifiers: None,
             include: None,
             logging_macros: None,
+            seed: None,
         }
     }
 
@@ -900,6 +1052,8 @@ mod tests {
                 control_flow: false,
                 control_flow_files: None,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: Some(true),
                 skip_files: None,
                 skip_attributes: None,
@@ -910,6 +1064,7 @@ mod tests {
                 enabled,
                 ignore_messages,
             }),
+            seed: None,
         }
     }
 
@@ -922,6 +1077,8 @@ mod tests {
                 control_flow: true,
                 control_flow_files: patterns,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files: None,
                 skip_attributes: None,
@@ -929,6 +1086,7 @@ mod tests {
             identifiers: None,
             include: None,
             logging_macros: None,
+            seed: None,
         }
     }
 
@@ -941,6 +1099,8 @@ mod tests {
                 control_flow: flow,
                 control_flow_files: None,
                 dummy_branches: Some(dummy_branches),
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files: None,
                 skip_attributes: None,
@@ -948,6 +1108,55 @@ mod tests {
             identifiers: None,
             include: None,
             logging_macros: None,
+            seed: None,
+        }
+    }
+
+    fn cfg_with_flattening(flow: bool, flatten: bool) -> ObfuscateConfig {
+        ObfuscateConfig {
+            obfuscation: ObfuscationSection {
+                strings: false,
+                min_string_length: None,
+                ignore_strings: None,
+                control_flow: flow,
+                control_flow_files: None,
+                dummy_branches: None,
+                flatten_control_flow: Some(flatten),
+                indirect_calls: None,
+                obfuscate_logging: None,
+                skip_files: None,
+                skip_attributes: None,
+            },
+            identifiers: None,
+            include: None,
+            logging_macros: None,
+            seed: None,
+        }
+    }
+
+    fn cfg_with_indirect_calls(names: &[&str], rename: bool) -> ObfuscateConfig {
+        ObfuscateConfig {
+            obfuscation: ObfuscationSection {
+                strings: false,
+                min_string_length: None,
+                ignore_strings: None,
+                control_flow: false,
+                control_flow_files: None,
+                dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: Some(names.iter().map(|name| name.to_string()).collect()),
+                obfuscate_logging: None,
+                skip_files: None,
+                skip_attributes: None,
+            },
+            identifiers: Some(crate::config::IdentifiersSection {
+                rename,
+                strategy: None,
+                preserve: None,
+            }),
+            include: None,
+            logging_macros: None,
+            seed: None,
         }
     }
 
@@ -960,6 +1169,8 @@ mod tests {
                 control_flow: false,
                 control_flow_files: None,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files: None,
                 skip_attributes: None,
@@ -971,6 +1182,7 @@ mod tests {
             }),
             include: None,
             logging_macros: None,
+            seed: None,
         }
     }
 
@@ -983,6 +1195,8 @@ mod tests {
                 control_flow: false,
                 control_flow_files: None,
                 dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
                 obfuscate_logging: None,
                 skip_files: None,
                 skip_attributes: None,
@@ -994,6 +1208,7 @@ mod tests {
             }),
             include: None,
             logging_macros: None,
+            seed: None,
         }
     }
 
@@ -1012,9 +1227,12 @@ mod tests {
             rename_identifiers: rename_identifiers,
             preserve_idents: vec![],
             rename_strategy: RenameStrategy::Suffix,
+            rename_seed: None,
             obfuscate_strings: obfuscate_strings,
             obfuscate_flow: obfuscate_flow,
             obfuscate_dummy_branches: false,
+            flatten_control_flow: false,
+            indirect_calls: HashSet::new(),
             obfuscate_logging: false,
             logging_macros: default_logging_macros().into_iter().collect(),
             ignore_logging_messages: vec![],
@@ -1350,6 +1568,249 @@ pub const TEST: &str = "test";"#;
         assert!(!out.contains("obfuscate_dummy_branch!();"));
     }
 
+    #[test]
+    fn flattening_marks_runtime_fns_on_top_of_control_flow() {
+        let src = r#"pub fn free() {}
+
+pub const fn fixed() -> u8 {
+    1
+}
+
+struct S;
+
+impl S {
+    fn method(&self) {}
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_flattening(true, true),
+            false,
+        )
+        .unwrap();
+
+        assert!(!out.contains("use rust_code_obfuscator::obfuscate;"));
+        assert_eq!(
+            out.matches("#[rust_code_obfuscator::obfuscate(flatten)]")
+                .count(),
+            2
+        );
+        assert!(out.contains("#[rust_code_obfuscator::obfuscate(flatten)]\npub fn free()"));
+        assert!(out.contains("#[rust_code_obfuscator::obfuscate(flatten)]\n    fn method(&self)"));
+    }
+
+    #[test]
+    fn flattening_marks_fns_in_inline_modules_with_the_full_path() {
+        let src = r#"mod outer {
+    mod inner {
+        pub fn nested() {}
+    }
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_flattening(true, true),
+            false,
+        )
+        .unwrap();
+
+        assert!(
+            out.contains("#[rust_code_obfuscator::obfuscate(flatten)]\n        pub fn nested()"),
+            "nested fn is not marked with the full path:\n{out}"
+        );
+        assert!(!out.contains("use rust_code_obfuscator::obfuscate;"));
+    }
+
+    #[test]
+    fn flattening_does_not_run_when_control_flow_is_disabled() {
+        let src = r#"pub fn free() {}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_flattening(false, true),
+            false,
+        )
+        .unwrap();
+
+        assert!(!out.contains("obfuscate"));
+    }
+
+    #[test]
+    fn flattening_is_off_unless_configured() {
+        let src = r#"pub fn free() {}"#;
+        let mut cfg = cfg_with_flattening(true, false);
+        cfg.obfuscation.flatten_control_flow = None;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) =
+            super::process_file(&path, relative_path.as_path(), &cfg, false).unwrap();
+
+        assert!(!out.contains("obfuscate(flatten)"));
+    }
+
+    #[test]
+    fn flattening_keeps_existing_obfuscate_attributes() {
+        let src = r#"use rust_code_obfuscator::obfuscate;
+
+#[obfuscate(strings)]
+pub fn free() {}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_flattening(true, true),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            out.matches("use rust_code_obfuscator::obfuscate;").count(),
+            1
+        );
+        assert!(out.contains("#[obfuscate(strings)]"));
+        assert!(!out.contains("obfuscate(flatten)"));
+    }
+
+    #[test]
+    fn indirect_calls_mark_fns_calling_the_selected_functions() {
+        let src = r#"fn check(key: &str) -> bool {
+    key.len() == 8
+}
+
+fn decrypt(data: u8) -> u8 {
+    data ^ 1
+}
+
+pub fn run(key: &str) -> u8 {
+    if check(key) && check("fallback") { decrypt(2) } else { 0 }
+}
+
+pub fn idle() {}
+
+struct S;
+
+impl S {
+    fn method(&self) -> bool {
+        check("x")
+    }
+
+    fn qualified(&self) -> bool {
+        crate::check("y")
+    }
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_indirect_calls(&["check", "decrypt"], false),
+            false,
+        )
+        .unwrap();
+
+        assert!(!out.contains("use rust_code_obfuscator::obfuscate_calls;"));
+        assert_eq!(
+            out.matches("#[rust_code_obfuscator::obfuscate_calls(")
+                .count(),
+            2
+        );
+        assert!(
+            out.contains("#[rust_code_obfuscator::obfuscate_calls(check, decrypt)]\npub fn run(")
+        );
+        assert!(
+            out.contains("#[rust_code_obfuscator::obfuscate_calls(check)]\n    fn method(&self)")
+        );
+    }
+
+    #[test]
+    fn indirect_calls_follow_renamed_functions() {
+        let src = r#"fn check(key: u32) -> bool {
+    key == 7
+}
+
+pub fn run(key: u32) -> bool {
+    check(key)
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_indirect_calls(&["check"], true),
+            false,
+        )
+        .unwrap();
+
+        assert!(
+            out.contains("#[rust_code_obfuscator::obfuscate_calls(check_obf_"),
+            "attribute does not name the renamed function:\n{out}"
+        );
+        assert!(!out.contains("obfuscate_calls(check)"));
+    }
+
+    #[test]
+    fn indirect_calls_mark_fns_in_inline_modules_with_the_full_path() {
+        let src = r#"mod outer {
+    mod inner {
+        fn check() {}
+
+        pub fn run() {
+            check()
+        }
+    }
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_indirect_calls(&["check"], false),
+            false,
+        )
+        .unwrap();
+
+        assert!(
+            out.contains("#[rust_code_obfuscator::obfuscate_calls(check)]\n        pub fn run()"),
+            "nested fn is not marked with the full path:\n{out}"
+        );
+        assert!(!out.contains("use rust_code_obfuscator::obfuscate_calls;"));
+    }
+
+    #[test]
+    fn indirect_calls_keep_existing_attributes() {
+        let src = r#"use rust_code_obfuscator::obfuscate_calls;
+
+fn check() {}
+
+#[obfuscate_calls(check)]
+pub fn run() {
+    check()
+}"#;
+
+        let (_dir, path, relative_path) = create_rs_file(src);
+        let (out, _changed, _before) = super::process_file(
+            &path,
+            relative_path.as_path(),
+            &cfg_with_indirect_calls(&["check"], false),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            out.matches("use rust_code_obfuscator::obfuscate_calls;")
+                .count(),
+            1
+        );
+        assert_eq!(out.matches("#[obfuscate_calls(check)]").count(), 1);
+    }
+
     #[test]
     fn inner_docs_stay_first_and_compileable_transform() {
         let src = r#"//! Crate docs
@@ -1643,6 +2104,46 @@ pub fn call() -> u32 {
         assert!(out.contains("pub fn _"), "{out}");
     }
 
+    #[test]
+    fn rename_strategies_are_reproducible_with_a_seed() {
+        let src = r#"pub fn sensitive_name(secret_value: u32) -> u32 {
+    secret_value
+}
+"#;
+        for strategy in ["suffix", "hash", "confuse"] {
+            let mut cfg = cfg_with_rename_strategy(strategy, None);
+            cfg.seed = Some("release-1".to_string());
+            let run = |cfg: &ObfuscateConfig| {
+                let (_dir, path, relative_path) = create_rs_file(src);
+                super::process_file(&path, relative_path.as_path(), cfg, false)
+                    .unwrap()
+                    .0
+            };
+
+            let first = run(&cfg);
+            assert_eq!(first, run(&cfg), "{strategy}");
+
+            cfg.seed = Some("release-2".to_string());
+            assert_ne!(first, run(&cfg), "{strategy}");
+        }
+    }
+
+    #[test]
+    fn stable_base36_does_not_depend_on_std_hasher() {
+        assert_eq!(
+            stable_base36("secret_value", RenameKind::Binding, 0, None),
+            stable_base36("secret_value", RenameKind::Binding, 0, None)
+        );
+        assert_ne!(
+            stable_base36("secret_value", RenameKind::Binding, 0, None),
+            stable_base36("secret_value", RenameKind::Function, 0, None)
+        );
+        assert_ne!(
+            stable_base36("secret_value", RenameKind::Binding, 0, None),
+            stable_base36("secret_value", RenameKind::Binding, 0, Some("seed"))
+        );
+    }
+
     #[test]
     fn rename_strategy_respects_preserve_list() {
         let src = r#"pub fn main() {
@@ -227,11 +227,18 @@ mod tests{
                 min_string_length: None, 
                 ignore_strings: None, 
                 control_flow: true,
+                control_flow_files: None,
+                dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
+                obfuscate_logging: None,
                 skip_files: None, 
                 skip_attributes: None
             }, 
             identifiers: None, 
-            include: None };
+            include: None,
+            logging_macros: None,
+            seed: None };
 
         let result = transform_rust_files(&path, &config, false, true, None, false);
         match  result {
@@ -278,13 +285,20 @@ match x {
                 min_string_length: None, 
                 ignore_strings: None, 
                 control_flow: true,
+                control_flow_files: None,
+                dummy_branches: None,
+                flatten_control_flow: None,
+                indirect_calls: None,
+                obfuscate_logging: None,
                 skip_files: None, 
                 skip_attributes: None
             }, 
             identifiers: None, 
-            include: None };
+            include: None,
+            logging_macros: None,
+            seed: None };
 
-        let result = transform_rust_files(&dir.path(), &config, false, false, None, false);
+        let result = transform_rust_files(dir.path(), &config, false, false, None, false);
         match  result {
             Ok(_) => {},
             Err(_) => panic!("transform_rust_files fails with error"),
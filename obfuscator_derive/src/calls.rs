@@ -0,0 +1,306 @@
+//! `#[obfuscate_calls(...)]`: routes calls to the selected functions through
+//! tables of function pointers.
+//!
+//! Every rewritten call site builds its own table on the stack and passes it
+//! through `black_box`, so the optimizer cannot fold the load back into a
+//! direct call. The target sits at a random slot among decoy functions from
+//! `core`, and the slot is decoded at runtime from an encoded constant. The
+//! loaded pointer is cast back to the target's own signature, inferred from
+//! the call like the direct call was, so types and behaviour do not change.
+//!
+//! Only plain path calls with at most [`MAX_ARGS`] arguments are rewritten,
+//! and only when the call spells the target's path exactly: `auth::check`
+//! selects `auth::check(x)` but not `check(x)` or `other::check(x)`. A bare
+//! name selects unqualified calls that are not to a local binding of the same
+//! name. Method-call syntax, constant contexts and macro arguments are left
+//! alone.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::Visit,
+    visit_mut::VisitMut,
+    Expr, ExprCall, ExprPath, FnArg, Ident, Item, Pat, Path, Token,
+};
+
+use crate::literal::literal_rng;
+
+/// Highest arity with an `__indirectN` helper in the core crate.
+const MAX_ARGS: usize = 8;
+
+/// Arguments of `#[obfuscate_calls(...)]`: the functions whose calls are
+/// rerouted.
+pub(crate) struct Targets(Vec<Path>);
+
+impl Parse for Targets {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let paths = Punctuated::<Path, Token![,]>::parse_terminated(input)?;
+        if paths.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected the functions to reroute, e.g. #[obfuscate_calls(check_license)]",
+            ));
+        }
+        Ok(Self(paths.into_iter().collect()))
+    }
+}
+
+pub(crate) fn expand(targets: Targets, mut item: Item) -> syn::Result<TokenStream> {
+    match &item {
+        Item::Fn(_) | Item::Impl(_) => {}
+        Item::Mod(module) if module.content.is_some() => {}
+        other => {
+            return Err(syn::Error::new_spanned(
+                other,
+                "#[obfuscate_calls] applies to a fn, an impl block or an inline module",
+            ))
+        }
+    }
+
+    let seed = quote! { #item }.to_string();
+    let mut rewriter = CallRewriter {
+        targets: targets.0,
+        rng: literal_rng(item.span(), seed.as_bytes()),
+        locals: Vec::new(),
+        error: None,
+    };
+    rewriter.visit_item_mut(&mut item);
+    match rewriter.error {
+        Some(error) => Err(error),
+        None => Ok(quote! { #item }),
+    }
+}
+
+struct CallRewriter {
+    targets: Vec<Path>,
+    rng: StdRng,
+    /// Names bound by patterns in scope at the visited expression.
+    locals: Vec<Ident>,
+    error: Option<syn::Error>,
+}
+
+impl CallRewriter {
+    /// Whether `path` names one of the targets. The segments must match
+    /// exactly, generic arguments aside, and a single name must not be
+    /// shadowed by a local binding.
+    fn selects(&self, path: &Path) -> bool {
+        let selected = self.targets.iter().any(|target| {
+            target.leading_colon.is_some() == path.leading_colon.is_some()
+                && target.segments.len() == path.segments.len()
+                && target
+                    .segments
+                    .iter()
+                    .zip(&path.segments)
+                    .all(|(wanted, seen)| wanted.ident == seen.ident)
+        });
+        let shadowed = path.leading_colon.is_none()
+            && path.segments.len() == 1
+            && self.locals.contains(&path.segments[0].ident);
+        selected && !shadowed
+    }
+
+    fn bind(&mut self, pat: &Pat) {
+        Bindings(&mut self.locals).visit_pat(pat);
+    }
+
+    /// Visits a fn body with its parameters in scope. Nested items do not
+    /// see the locals of the fn around them.
+    fn visit_fn_body(&mut self, sig: &syn::Signature, block: &mut syn::Block) {
+        let outer = std::mem::take(&mut self.locals);
+        for input in &sig.inputs {
+            if let FnArg::Typed(arg) = input {
+                self.bind(&arg.pat);
+            }
+        }
+        self.visit_block_mut(block);
+        self.locals = outer;
+    }
+
+    fn reroute(&mut self, call: &ExprCall, target: &ExprPath) -> Expr {
+        let mut table = decoys(&mut self.rng);
+        let slot = self.rng.random_range(0..=table.len());
+        table.insert(slot, quote! { rustfuscator_target as *const () });
+        let len = table.len();
+
+        let key: u64 = self.rng.random();
+        let encoded = slot as u64 ^ key;
+        let helper = format_ident!("__indirect{}", call.args.len());
+        let args = &call.args;
+
+        syn::parse_quote! {
+            ({
+                let rustfuscator_target = #target;
+                let rustfuscator_table: [*const (); #len] =
+                    ::core::hint::black_box([#(#table),*]);
+                let rustfuscator_slot = (::core::hint::black_box(#encoded) ^ #key) as usize;
+                unsafe {
+                    rust_code_obfuscator::obfuscator::#helper(
+                        rustfuscator_target,
+                        rustfuscator_table[rustfuscator_slot],
+                    )
+                }
+            })(#args)
+        }
+    }
+}
+
+/// Two to four distinct functions from `core` to share the table with.
+fn decoys(rng: &mut StdRng) -> Vec<TokenStream> {
+    let mut pool = vec![
+        quote! { ::core::mem::drop::<u64> as *const () },
+        quote! { ::core::hint::black_box::<u64> as *const () },
+        quote! { <u32>::count_ones as *const () },
+        quote! { <u64>::rotate_left as *const () },
+        quote! { <u8>::wrapping_add as *const () },
+        quote! { <i64>::wrapping_mul as *const () },
+        quote! { <u16>::swap_bytes as *const () },
+        quote! { <u128>::leading_zeros as *const () },
+        quote! { <usize>::checked_sub as *const () },
+        quote! { <i32>::saturating_sub as *const () },
+    ];
+    pool.shuffle(rng);
+    pool.truncate(rng.random_range(2..=4));
+    pool
+}
+
+/// Collects the names a pattern binds.
+struct Bindings<'a>(&'a mut Vec<Ident>);
+
+impl<'ast> Visit<'ast> for Bindings<'_> {
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        self.0.push(node.ident.clone());
+        syn::visit::visit_pat_ident(self, node);
+    }
+
+    fn visit_expr(&mut self, _: &'ast Expr) {}
+
+    fn visit_type(&mut self, _: &'ast syn::Type) {}
+}
+
+impl VisitMut for CallRewriter {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        let scope = self.locals.len();
+        match expr {
+            Expr::Repeat(node) => self.visit_expr_mut(&mut node.expr),
+            Expr::Const(_) => {}
+            // `let` expressions bind for the rest of the `if` / `while`
+            // condition and for its body.
+            Expr::Let(node) => {
+                self.visit_expr_mut(&mut node.expr);
+                self.bind(&node.pat);
+            }
+            Expr::If(node) => {
+                self.visit_expr_mut(&mut node.cond);
+                self.visit_block_mut(&mut node.then_branch);
+                self.locals.truncate(scope);
+                if let Some((_, else_branch)) = &mut node.else_branch {
+                    self.visit_expr_mut(else_branch);
+                }
+            }
+            Expr::While(node) => {
+                self.visit_expr_mut(&mut node.cond);
+                self.visit_block_mut(&mut node.body);
+                self.locals.truncate(scope);
+            }
+            Expr::ForLoop(node) => {
+                self.visit_expr_mut(&mut node.expr);
+                self.bind(&node.pat);
+                self.visit_block_mut(&mut node.body);
+                self.locals.truncate(scope);
+            }
+            Expr::Closure(node) => {
+                for input in &node.inputs {
+                    self.bind(input);
+                }
+                self.visit_expr_mut(&mut node.body);
+                self.locals.truncate(scope);
+            }
+            _ => syn::visit_mut::visit_expr_mut(self, expr),
+        }
+
+        let Expr::Call(call) = expr else {
+            return;
+        };
+        let Expr::Path(target) = &*call.func else {
+            return;
+        };
+        if target.qself.is_some() || !self.selects(&target.path) {
+            return;
+        }
+        if call.args.len() > MAX_ARGS {
+            if self.error.is_none() {
+                self.error = Some(syn::Error::new_spanned(
+                    &call.args,
+                    format!("#[obfuscate_calls] reroutes calls with at most {MAX_ARGS} arguments"),
+                ));
+            }
+            return;
+        }
+        *expr = self.reroute(call, target);
+    }
+
+    fn visit_block_mut(&mut self, node: &mut syn::Block) {
+        let scope = self.locals.len();
+        syn::visit_mut::visit_block_mut(self, node);
+        self.locals.truncate(scope);
+    }
+
+    fn visit_local_mut(&mut self, node: &mut syn::Local) {
+        if let Some(init) = &mut node.init {
+            self.visit_local_init_mut(init);
+        }
+        self.bind(&node.pat);
+    }
+
+    fn visit_arm_mut(&mut self, node: &mut syn::Arm) {
+        let scope = self.locals.len();
+        self.bind(&node.pat);
+        if let Some((_, guard)) = &mut node.guard {
+            self.visit_expr_mut(guard);
+        }
+        self.visit_expr_mut(&mut node.body);
+        self.locals.truncate(scope);
+    }
+
+    fn visit_item_fn_mut(&mut self, node: &mut syn::ItemFn) {
+        if node.sig.constness.is_none() {
+            self.visit_fn_body(&node.sig, &mut node.block);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, node: &mut syn::ImplItemFn) {
+        if node.sig.constness.is_none() {
+            self.visit_fn_body(&node.sig, &mut node.block);
+        }
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, node: &mut syn::TraitItemFn) {
+        if let (None, Some(block)) = (node.sig.constness, &mut node.default) {
+            self.visit_fn_body(&node.sig, block);
+        }
+    }
+
+    fn visit_item_const_mut(&mut self, _: &mut syn::ItemConst) {}
+
+    fn visit_item_static_mut(&mut self, _: &mut syn::ItemStatic) {}
+
+    fn visit_impl_item_const_mut(&mut self, _: &mut syn::ImplItemConst) {}
+
+    fn visit_trait_item_const_mut(&mut self, _: &mut syn::TraitItemConst) {}
+
+    fn visit_variant_mut(&mut self, _: &mut syn::Variant) {}
+
+    fn visit_type_mut(&mut self, _: &mut syn::Type) {}
+
+    fn visit_pat_mut(&mut self, _: &mut syn::Pat) {}
+
+    fn visit_generic_argument_mut(&mut self, _: &mut syn::GenericArgument) {}
+
+    fn visit_attribute_mut(&mut self, _: &mut syn::Attribute) {}
+
+    fn visit_macro_mut(&mut self, _: &mut syn::Macro) {}
+}
@@ -0,0 +1,748 @@
+//! Control-flow flattening for `#[obfuscate(flatten)]`.
+//!
+//! Each block is cut into runs of statements, and every run of two or more
+//! becomes a dispatcher: a `loop` over a `match` on a state variable, with one
+//! arm per statement and separate arms for the condition and both branches of
+//! a plain `if`. States are random `u64`s stored XOR-ed with a per-dispatcher
+//! key and read back through `black_box`, and the arms are emitted in random
+//! order, so the original sequence cannot be recovered by constant folding.
+//!
+//! The borrow checker does not know that each arm runs at most once, so
+//! anything it would only accept outside a loop stays in the block between
+//! dispatchers: `let` statements, items, statements that may move or
+//! re-initialise a local, unknown macros, and the last statement of a block
+//! without a tail expression (it may diverge). Whether a statement may move is
+//! decided syntactically and conservatively. Unlabelled `break` / `continue`
+//! are labelled first, so they still reach the user's loop from inside a
+//! dispatcher; `return` and `?` need no changes.
+
+use std::collections::HashSet;
+
+use proc_macro2::{Literal, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use syn::{
+    parse::Parser, punctuated::Punctuated, visit::Visit, visit_mut::VisitMut, BinOp, Block, Expr,
+    Label, Lifetime, Lit, Local, Macro, Pat, Signature, Stmt, Token, Type, UnOp,
+};
+
+/// Macros whose arguments are plain expressions that are only borrowed, or
+/// that take no local at all. Any other macro may bind or move, so its
+/// statement stays outside the dispatchers.
+const KNOWN_MACROS: &[&str] = &[
+    "print",
+    "println",
+    "eprint",
+    "eprintln",
+    "format",
+    "write",
+    "writeln",
+    "panic",
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "unreachable",
+    "todo",
+    "unimplemented",
+    "trace",
+    "debug",
+    "info",
+    "warn",
+    "error",
+    "obfuscate_flow",
+    "obfuscate_dummy_branch",
+    "obfuscate_str",
+    "obfuscate_string",
+    "obfuscate_num",
+];
+
+/// Standard methods that take `&self` / `&mut self`, so calling them does not
+/// move the receiver.
+const BORROWING_METHODS: &[&str] = &[
+    "as_bytes",
+    "as_mut",
+    "as_ref",
+    "as_slice",
+    "as_str",
+    "borrow",
+    "borrow_mut",
+    "bytes",
+    "capacity",
+    "chars",
+    "clear",
+    "clone",
+    "contains",
+    "contains_key",
+    "dedup",
+    "ends_with",
+    "entry",
+    "extend",
+    "first",
+    "get",
+    "get_mut",
+    "insert",
+    "is_empty",
+    "is_err",
+    "is_none",
+    "is_ok",
+    "is_some",
+    "iter",
+    "iter_mut",
+    "keys",
+    "last",
+    "len",
+    "lines",
+    "lock",
+    "pop",
+    "push",
+    "push_str",
+    "remove",
+    "reserve",
+    "retain",
+    "reverse",
+    "sort",
+    "sort_unstable",
+    "split",
+    "starts_with",
+    "to_owned",
+    "to_string",
+    "to_vec",
+    "trim",
+    "truncate",
+    "values",
+];
+
+const COPY_PRIMITIVES: &[&str] = &[
+    "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize", "f32", "f64",
+];
+
+pub(crate) struct Flattener {
+    rng: StdRng,
+    dispatchers: usize,
+}
+
+impl Flattener {
+    pub(crate) fn new(rng: StdRng) -> Self {
+        Self {
+            rng,
+            dispatchers: 0,
+        }
+    }
+
+    /// Flattens a fn body in place. `const fn` bodies are left alone.
+    pub(crate) fn flatten_fn(&mut self, sig: &Signature, block: &mut Block) {
+        if sig.constness.is_some() {
+            return;
+        }
+
+        LoopLabels::default().visit_block_mut(block);
+        self.flatten_block(block, &Scope::for_signature(sig));
+    }
+
+    fn flatten_block(&mut self, block: &mut Block, scope: &Scope) {
+        let mut scope = scope.clone();
+        let stmts = std::mem::take(&mut block.stmts);
+        let count = stmts.len();
+        let mut run = Vec::new();
+
+        for (index, mut stmt) in stmts.into_iter().enumerate() {
+            self.flatten_nested_stmt(&mut stmt, &scope);
+            if index + 1 < count && can_dispatch(&stmt, &scope) {
+                run.push(stmt);
+                continue;
+            }
+
+            self.flush(&mut run, &mut block.stmts);
+            if let Stmt::Local(local) = &stmt {
+                scope.bind_local(local);
+            }
+            block.stmts.push(stmt);
+        }
+        self.flush(&mut run, &mut block.stmts);
+    }
+
+    fn flatten_nested_stmt(&mut self, stmt: &mut Stmt, scope: &Scope) {
+        match stmt {
+            Stmt::Local(local) => {
+                if let Some(init) = &mut local.init {
+                    self.flatten_nested_expr(&mut init.expr, scope);
+                    if let Some((_, diverge)) = &mut init.diverge {
+                        self.flatten_nested_expr(diverge, scope);
+                    }
+                }
+            }
+            Stmt::Expr(expr, _) => self.flatten_nested_expr(expr, scope),
+            Stmt::Item(_) | Stmt::Macro(_) => {}
+        }
+    }
+
+    fn flatten_nested_expr(&mut self, expr: &mut Expr, scope: &Scope) {
+        match expr {
+            Expr::Block(node) => self.flatten_block(&mut node.block, scope),
+            Expr::Unsafe(node) => self.flatten_block(&mut node.block, scope),
+            Expr::Loop(node) => self.flatten_block(&mut node.body, scope),
+            Expr::If(node) => {
+                let mut inner = scope.clone();
+                inner.bind_condition(&node.cond);
+                self.flatten_block(&mut node.then_branch, &inner);
+                if let Some((_, else_branch)) = &mut node.else_branch {
+                    self.flatten_nested_expr(else_branch, scope);
+                }
+            }
+            Expr::While(node) => {
+                let mut inner = scope.clone();
+                inner.bind_condition(&node.cond);
+                self.flatten_block(&mut node.body, &inner);
+            }
+            Expr::ForLoop(node) => {
+                let mut inner = scope.clone();
+                inner.bind_pat(&node.pat, Binding::Owned);
+                self.flatten_block(&mut node.body, &inner);
+            }
+            Expr::Match(node) => {
+                for arm in &mut node.arms {
+                    let mut inner = scope.clone();
+                    inner.bind_pat(&arm.pat, Binding::Owned);
+                    self.flatten_nested_expr(&mut arm.body, &inner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces a run of dispatchable statements with its dispatcher, or
+    /// keeps it as is when there is nothing to shuffle.
+    fn flush(&mut self, run: &mut Vec<Stmt>, out: &mut Vec<Stmt>) {
+        let states: usize = run
+            .iter()
+            .map(|stmt| if split_if(stmt).is_some() { 3 } else { 1 })
+            .sum();
+        if states < 2 {
+            out.append(run);
+            return;
+        }
+
+        let dispatcher = self.dispatcher(std::mem::take(run));
+        out.push(Stmt::Expr(
+            syn::parse2(dispatcher).expect("dispatcher is a block expression"),
+            None,
+        ));
+    }
+
+    fn dispatcher(&mut self, run: Vec<Stmt>) -> TokenStream {
+        self.dispatchers += 1;
+        let state = format_ident!("rustfuscator_state_{}", self.dispatchers);
+        let label = Lifetime::new(
+            &format!("'rustfuscator_dispatch_{}", self.dispatchers),
+            Span::call_site(),
+        );
+        let key: u64 = self.rng.random();
+        let mut ids = StateIds {
+            rng: &mut self.rng,
+            used: HashSet::new(),
+        };
+        let encode = |id: u64| Literal::u64_suffixed(id ^ key);
+
+        let entry = ids.fresh();
+        let mut current = entry;
+        let mut arms = Vec::new();
+        for stmt in run {
+            let next = ids.fresh();
+            match split_if(&stmt) {
+                Some((cond, then_branch, else_branch)) => {
+                    let then_id = ids.fresh();
+                    let else_id = match else_branch {
+                        Some(else_branch) => {
+                            let else_id = ids.fresh();
+                            let goto = encode(next);
+                            arms.push((else_id, quote! { #else_branch; #state = #goto; }));
+                            else_id
+                        }
+                        None => next,
+                    };
+                    let (to_then, to_else, goto) = (encode(then_id), encode(else_id), encode(next));
+                    arms.push((
+                        current,
+                        quote! { #state = if #cond { #to_then } else { #to_else }; },
+                    ));
+                    arms.push((then_id, quote! { #then_branch; #state = #goto; }));
+                }
+                None => {
+                    let goto = encode(next);
+                    arms.push((current, quote! { #stmt #state = #goto; }));
+                }
+            }
+            current = next;
+        }
+        arms.push((current, quote! { break #label; }));
+        arms.shuffle(&mut self.rng);
+
+        let arms = arms.into_iter().map(|(id, body)| {
+            let id = Literal::u64_suffixed(id);
+            quote! { #id => { #body } }
+        });
+        let entry = encode(entry);
+        let key = Literal::u64_suffixed(key);
+        // Statements after a diverging one still get a state transition.
+        quote! {
+            #[allow(unreachable_code)]
+            {
+                let mut #state: u64 = #entry;
+                #label: loop {
+                    match ::core::hint::black_box(#state) ^ #key {
+                        #(#arms)*
+                        _ => ::core::unreachable!(),
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct StateIds<'a> {
+    rng: &'a mut StdRng,
+    used: HashSet<u64>,
+}
+
+impl StateIds<'_> {
+    fn fresh(&mut self) -> u64 {
+        loop {
+            let id = self.rng.random();
+            if self.used.insert(id) {
+                return id;
+            }
+        }
+    }
+}
+
+/// Splits a statement-position `if` without `let` in its condition into the
+/// condition and both branches.
+fn split_if(stmt: &Stmt) -> Option<(&Expr, &Block, Option<&Expr>)> {
+    let Stmt::Expr(Expr::If(node), _) = stmt else {
+        return None;
+    };
+    if contains_let(&node.cond) {
+        return None;
+    }
+    Some((
+        &node.cond,
+        &node.then_branch,
+        node.else_branch
+            .as_ref()
+            .map(|(_, else_branch)| &**else_branch),
+    ))
+}
+
+fn contains_let(expr: &Expr) -> bool {
+    match expr {
+        Expr::Let(_) => true,
+        Expr::Binary(node) => contains_let(&node.left) || contains_let(&node.right),
+        Expr::Paren(node) => contains_let(&node.expr),
+        _ => false,
+    }
+}
+
+fn can_dispatch(stmt: &Stmt, scope: &Scope) -> bool {
+    match stmt {
+        Stmt::Local(_) | Stmt::Item(_) => false,
+        Stmt::Macro(node) => {
+            is_known_macro(&node.mac) && !MoveScan::finds(scope, |scan| scan.visit_macro(&node.mac))
+        }
+        Stmt::Expr(expr, _) => !MoveScan::finds(scope, |scan| scan.visit_expr(expr)),
+    }
+}
+
+fn is_known_macro(mac: &Macro) -> bool {
+    mac.path
+        .segments
+        .last()
+        .is_some_and(|segment| KNOWN_MACROS.contains(&segment.ident.to_string().as_str()))
+}
+
+/// How a local binding behaves when used by value.
+#[derive(Clone, Copy)]
+enum Binding {
+    /// Known to be `Copy`: a primitive, a shared reference or a literal.
+    Copy,
+    /// A `&mut` reference: reborrowed as a method receiver, moved otherwise.
+    Reborrow,
+    /// Anything else, which may move.
+    Owned,
+    /// Declared without an initialiser.
+    Uninit,
+}
+
+/// Locals in scope that a dispatched statement must not move.
+#[derive(Clone, Default)]
+struct Scope {
+    owned: HashSet<String>,
+    reborrowed: HashSet<String>,
+    uninit: HashSet<String>,
+}
+
+impl Scope {
+    fn for_signature(sig: &Signature) -> Self {
+        let mut scope = Self::default();
+        for input in &sig.inputs {
+            match input {
+                syn::FnArg::Receiver(receiver) => {
+                    scope.bind_name("self".to_string(), binding_for_type(&receiver.ty))
+                }
+                syn::FnArg::Typed(arg) => scope.bind_pat(&arg.pat, binding_for_type(&arg.ty)),
+            }
+        }
+        scope
+    }
+
+    fn bind_local(&mut self, local: &Local) {
+        let (pat, ty) = match &local.pat {
+            Pat::Type(typed) => (&*typed.pat, Some(&*typed.ty)),
+            pat => (pat, None),
+        };
+        let binding = match (&local.init, ty) {
+            (None, _) => Binding::Uninit,
+            (Some(_), Some(ty)) => binding_for_type(ty),
+            (Some(init), None) if is_copy_literal(&init.expr) => Binding::Copy,
+            (Some(_), None) => Binding::Owned,
+        };
+        self.bind_pat(pat, binding);
+    }
+
+    fn bind_condition(&mut self, cond: &Expr) {
+        match cond {
+            Expr::Let(node) => self.bind_pat(&node.pat, Binding::Owned),
+            Expr::Binary(node) => {
+                self.bind_condition(&node.left);
+                self.bind_condition(&node.right);
+            }
+            Expr::Paren(node) => self.bind_condition(&node.expr),
+            _ => {}
+        }
+    }
+
+    fn bind_pat(&mut self, pat: &Pat, binding: Binding) {
+        // Only a plain identifier takes the binding of a type annotation;
+        // names bound by destructuring are treated as owned.
+        let binding = match (pat, binding) {
+            (_, Binding::Uninit) | (Pat::Ident(_), _) => binding,
+            (Pat::Type(typed), _) => return self.bind_pat(&typed.pat, binding),
+            _ => Binding::Owned,
+        };
+        let mut names = PatNames::default();
+        names.visit_pat(pat);
+        for name in names.0 {
+            self.bind_name(name, binding);
+        }
+    }
+
+    fn bind_name(&mut self, name: String, binding: Binding) {
+        self.owned.remove(&name);
+        self.reborrowed.remove(&name);
+        self.uninit.remove(&name);
+        match binding {
+            Binding::Copy => {}
+            Binding::Reborrow => {
+                self.reborrowed.insert(name);
+            }
+            Binding::Owned => {
+                self.owned.insert(name);
+            }
+            Binding::Uninit => {
+                self.uninit.insert(name);
+            }
+        }
+    }
+
+    fn tracks(&self, name: &str) -> bool {
+        self.owned.contains(name) || self.reborrowed.contains(name) || self.uninit.contains(name)
+    }
+}
+
+fn binding_for_type(ty: &Type) -> Binding {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_some() => Binding::Reborrow,
+        Type::Reference(_) => Binding::Copy,
+        Type::Path(path)
+            if path.qself.is_none()
+                && COPY_PRIMITIVES
+                    .iter()
+                    .any(|primitive| path.path.is_ident(primitive)) =>
+        {
+            Binding::Copy
+        }
+        Type::Paren(inner) => binding_for_type(&inner.elem),
+        _ => Binding::Owned,
+    }
+}
+
+fn is_copy_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(lit) => matches!(
+            lit.lit,
+            Lit::Int(_) | Lit::Float(_) | Lit::Bool(_) | Lit::Char(_) | Lit::Byte(_)
+        ),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => is_copy_literal(&unary.expr),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct PatNames(Vec<String>);
+
+impl<'ast> Visit<'ast> for PatNames {
+    fn visit_pat_ident(&mut self, node: &'ast syn::PatIdent) {
+        self.0.push(node.ident.to_string());
+        syn::visit::visit_pat_ident(self, node);
+    }
+
+    fn visit_expr(&mut self, _: &'ast Expr) {}
+}
+
+/// Looks for uses of tracked locals that may move them or assign to them.
+struct MoveScan<'a> {
+    scope: &'a Scope,
+    closures: usize,
+    found: bool,
+}
+
+impl<'a> MoveScan<'a> {
+    fn finds(scope: &'a Scope, scan: impl FnOnce(&mut Self)) -> bool {
+        let mut scanner = Self {
+            scope,
+            closures: 0,
+            found: false,
+        };
+        scan(&mut scanner);
+        scanner.found
+    }
+
+    /// Scans an expression whose place is only borrowed, e.g. `&x.field`.
+    fn place(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Path(path) => {
+                if path_name(path).is_some_and(|name| self.scope.uninit.contains(&name)) {
+                    self.found = true;
+                }
+            }
+            Expr::Field(node) => self.place(&node.base),
+            Expr::Index(node) => {
+                self.place(&node.expr);
+                self.visit_expr(&node.index);
+            }
+            Expr::Paren(node) => self.place(&node.expr),
+            Expr::Unary(node) if matches!(node.op, UnOp::Deref(_)) => self.place(&node.expr),
+            _ => self.visit_expr(expr),
+        }
+    }
+
+    fn mentions(&self, tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => self.scope.tracks(&ident.to_string()),
+            TokenTree::Group(group) => self.mentions(group.stream()),
+            _ => false,
+        })
+    }
+}
+
+impl<'ast> Visit<'ast> for MoveScan<'_> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if self.found {
+            return;
+        }
+        match expr {
+            Expr::Path(path) => {
+                if path_name(path).is_some_and(|name| self.scope.tracks(&name)) {
+                    self.found = true;
+                }
+            }
+            Expr::Reference(node) => self.place(&node.expr),
+            Expr::MethodCall(node) => {
+                let borrowing = BORROWING_METHODS.contains(&node.method.to_string().as_str())
+                    || receiver_root(&node.receiver)
+                        .is_some_and(|name| self.scope.reborrowed.contains(&name));
+                if borrowing {
+                    self.place(&node.receiver);
+                } else {
+                    self.visit_expr(&node.receiver);
+                }
+                for arg in &node.args {
+                    self.visit_expr(arg);
+                }
+            }
+            Expr::Assign(node) => {
+                match &*node.left {
+                    Expr::Path(path) => {
+                        if path_name(path).is_some_and(|name| self.scope.tracks(&name)) {
+                            self.found = true;
+                        }
+                    }
+                    left => self.place(left),
+                }
+                self.visit_expr(&node.right);
+            }
+            Expr::Binary(node) if is_compound_assign(&node.op) => {
+                self.place(&node.left);
+                self.visit_expr(&node.right);
+            }
+            Expr::Binary(node) if is_comparison(&node.op) => {
+                self.place(&node.left);
+                self.place(&node.right);
+            }
+            Expr::Return(_) if self.closures == 0 => {}
+            Expr::Closure(node) if node.capture.is_some() => {
+                if self.mentions(quote! { #node }) {
+                    self.found = true;
+                }
+            }
+            Expr::Closure(node) => {
+                self.closures += 1;
+                self.visit_expr(&node.body);
+                self.closures -= 1;
+            }
+            _ => syn::visit::visit_expr(self, expr),
+        }
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if is_known_macro(mac) {
+            let parser = Punctuated::<Expr, Token![,]>::parse_terminated;
+            if let Ok(args) = parser.parse2(mac.tokens.clone()) {
+                for arg in &args {
+                    self.place(arg);
+                }
+                return;
+            }
+        }
+        if self.mentions(mac.tokens.clone()) {
+            self.found = true;
+        }
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {}
+}
+
+fn path_name(path: &syn::ExprPath) -> Option<String> {
+    if path.qself.is_some() {
+        return None;
+    }
+    path.path.get_ident().map(ToString::to_string).or_else(|| {
+        (path.path.segments.len() == 1 && path.path.segments[0].ident == "self")
+            .then(|| "self".to_string())
+    })
+}
+
+fn receiver_root(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => path_name(path),
+        Expr::Field(node) => receiver_root(&node.base),
+        Expr::Paren(node) => receiver_root(&node.expr),
+        _ => None,
+    }
+}
+
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
+
+fn is_comparison(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq(_) | BinOp::Ne(_) | BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_)
+    )
+}
+
+/// Labels every loop that is the target of an unlabelled `break` or
+/// `continue` and adds the label to those, so they skip any dispatcher loop
+/// placed in between. Closures, async blocks and nested items have their own
+/// loops and are left alone.
+#[derive(Default)]
+struct LoopLabels {
+    stack: Vec<(Lifetime, bool)>,
+    next: usize,
+}
+
+impl LoopLabels {
+    fn enter(&mut self, label: &Option<Label>) {
+        let name = match label {
+            Some(label) => label.name.clone(),
+            None => {
+                self.next += 1;
+                Lifetime::new(
+                    &format!("'rustfuscator_loop_{}", self.next),
+                    Span::call_site(),
+                )
+            }
+        };
+        self.stack.push((name, false));
+    }
+
+    fn exit(&mut self, label: &mut Option<Label>) {
+        let (name, used) = self.stack.pop().expect("loop label stack underflow");
+        if used && label.is_none() {
+            *label = Some(Label {
+                name,
+                colon_token: Default::default(),
+            });
+        }
+    }
+
+    fn target(&mut self, label: &mut Option<Lifetime>) {
+        if label.is_none() {
+            if let Some((name, used)) = self.stack.last_mut() {
+                *used = true;
+                *label = Some(name.clone());
+            }
+        }
+    }
+}
+
+impl VisitMut for LoopLabels {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Loop(node) => {
+                self.enter(&node.label);
+                self.visit_block_mut(&mut node.body);
+                self.exit(&mut node.label);
+            }
+            Expr::While(node) => {
+                self.visit_expr_mut(&mut node.cond);
+                self.enter(&node.label);
+                self.visit_block_mut(&mut node.body);
+                self.exit(&mut node.label);
+            }
+            Expr::ForLoop(node) => {
+                self.visit_expr_mut(&mut node.expr);
+                self.enter(&node.label);
+                self.visit_block_mut(&mut node.body);
+                self.exit(&mut node.label);
+            }
+            Expr::Break(node) => {
+                self.target(&mut node.label);
+                if let Some(value) = &mut node.expr {
+                    self.visit_expr_mut(value);
+                }
+            }
+            Expr::Continue(node) => self.target(&mut node.label),
+            Expr::Closure(_) | Expr::Async(_) | Expr::Const(_) => {}
+            _ => syn::visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_mut(&mut self, _: &mut syn::Item) {}
+}
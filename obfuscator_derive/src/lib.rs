@@ -1,15 +1,38 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Path, Type};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitBool, LitByteStr, LitInt,
+    LitStr, Path, Token, Type,
+};
+
+mod calls;
+mod flatten;
+mod literal;
+mod mba;
+mod num;
+mod opaque;
+mod transform;
+
+use literal::Padding;
 
 const SUPPORTED_SCALARS: &[&str] = &[
-    "bool", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize", "f32", "f64",
 ];
 
-#[proc_macro_derive(Obfuscate)]
+#[proc_macro_derive(Obfuscate, attributes(obfuscator))]
 pub fn derive_obfuscate(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
+    let options = match StructOptions::from_attrs(&input.attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let cipher = match &options.cipher {
+        Some(path) => quote! { #path },
+        None => quote! { rust_code_obfuscator::crypto::DefaultCipher },
+    };
     let vis = &input.vis;
     let obf_name = Ident::new(&format!("Obfuscated{}", name), name.span());
 
@@ -45,7 +68,7 @@ pub fn derive_obfuscate(input: TokenStream) -> TokenStream {
             return syn::Error::new_spanned(
                 &f.ty,
                 format!(
-                    "Obfuscate derive only supports String, bool, and integer scalar fields (field `{}` has unsupported type)",
+                    "Obfuscate derive only supports String, bool, char, integer and float scalar fields (field `{}` has unsupported type)",
                     f.ident.as_ref().unwrap()
                 ),
             )
@@ -57,73 +80,135 @@ pub fn derive_obfuscate(input: TokenStream) -> TokenStream {
     // Generated obfuscated struct fields
     let obf_fields = fields.iter().map(|f| {
         let name = &f.ident;
-        quote! { #name: (Vec<u8>, [u8; 12]) }
+        quote! {
+            #name: rust_code_obfuscator::crypto::Envelope
+        }
     });
 
     // Arguments for clear-text constructor
-    let clear_args = fields.iter().map(|f| {
-        let name = &f.ident;
-        let ty = match &f.ty {
-            Type::Path(p) if is_string_path(&p.path) => quote! { &str },
-            Type::Path(_) => {
-                let ty = &f.ty;
-                quote! { #ty }
+    let clear_args = fields
+        .iter()
+        .map(|f| {
+            let name = &f.ident;
+            let ty = match &f.ty {
+                Type::Path(p) if is_string_path(&p.path) => quote! { &str },
+                Type::Path(_) => {
+                    let ty = &f.ty;
+                    quote! { #ty }
+                }
+                _ => quote! { &str }, // never reached due to validation above
+            };
+            quote! { #name: #ty }
+        })
+        .collect::<Vec<_>>();
+
+    // Forwarded arguments for new_clear(...) -> new_clear_with(...)
+    let clear_arg_names: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+
+    // Every field is sealed under its own subkey of the provider's master key
+    let field_key = |f: &syn::Field, master: proc_macro2::TokenStream| {
+        let type_name = name.to_string();
+        let field_name = f.ident.as_ref().unwrap().to_string();
+        quote! {
+            rust_code_obfuscator::crypto::derive_subkey(
+                &#master,
+                &rust_code_obfuscator::crypto::KeyContext::field(
+                    ::core::module_path!(),
+                    #type_name,
+                    #field_name,
+                ),
+            )
+        }
+    };
+
+    // Each field's ciphertext is bound to the struct and field name, unless
+    // opted out with #[obfuscator(aad = false)]
+    let field_aad = |f: &syn::Field| {
+        if options.aad {
+            let type_name = name.to_string();
+            let field_name = f.ident.as_ref().unwrap().to_string();
+            quote! { &rust_code_obfuscator::crypto::field_aad(#type_name, #field_name) }
+        } else {
+            quote! { &[] }
+        }
+    };
+
+    // String fields are padded with #[obfuscator(pad)] / #[obfuscator(pad = N)];
+    // scalars are fixed-width already
+    let seal_string = |name: &Option<Ident>, field_key, field_aad| match options.pad {
+        Some(padding) => {
+            let padding = padding_tokens(padding);
+            quote! {
+                #name: rust_code_obfuscator::crypto::seal_string_padded::<#cipher>(
+                    #name,
+                    #padding,
+                    #field_aad,
+                    rustfuscator_key_id,
+                    &#field_key
+                )?
             }
-            _ => quote! { &str }, // never reached due to validation above
+        }
+        None => quote! {
+            #name: rust_code_obfuscator::crypto::seal_string::<#cipher>(
+                #name,
+                #field_aad,
+                rustfuscator_key_id,
+                &#field_key
+            )?
+        },
+    };
+    let open_string = |name: &Option<Ident>, field_key, field_aad| {
+        let open = if options.pad.is_some() {
+            quote! { open_string_padded }
+        } else {
+            quote! { open_string }
         };
-        quote! { #name: #ty }
-    });
+        quote! {
+            #name: rust_code_obfuscator::crypto::#open::<#cipher>(
+                &self.#name,
+                #field_aad,
+                &#field_key
+            )?
+        }
+    };
 
-    // Encryption in new_clear(...)
+    // Encryption in new_clear_with(...)
     let clear_encrypt = fields.iter().map(|f| {
         let name = &f.ident;
+        let field_key = field_key(f, quote! { rustfuscator_master });
+        let field_aad = field_aad(f);
         match &f.ty {
-            Type::Path(p) if is_string_path(&p.path) => quote! {
-                #name: rust_code_obfuscator::crypto::encrypt_string(
-                    #name,
-                    &rust_code_obfuscator::crypto::default_key()
-                ).expect("encryption failed")
-            },
+            Type::Path(p) if is_string_path(&p.path) => seal_string(name, field_key, field_aad),
             Type::Path(_) => quote! {
-                #name: rust_code_obfuscator::crypto::encrypt_display(
+                #name: rust_code_obfuscator::crypto::seal_scalar::<#cipher, _>(
                     #name,
-                    &rust_code_obfuscator::crypto::default_key()
-                ).expect("encryption failed")
-            },
-            _ => quote! {
-                #name: rust_code_obfuscator::crypto::encrypt_string(
-                    #name,
-                    &rust_code_obfuscator::crypto::default_key()
-                ).expect("encryption failed")
+                    #field_aad,
+                    rustfuscator_key_id,
+                    &#field_key
+                )?
             },
+            _ => seal_string(name, field_key, field_aad),
         }
     });
 
-    // Decryption in get_clear()
+    // Decryption in get_clear_with(...), using the key named by each envelope
     let decrypt_fields = fields.iter().map(|f| {
         let name = &f.ident;
+        let field_key = field_key(f, quote! { provider.master_key_for(self.#name.key_id())? });
+        let field_aad = field_aad(f);
         match &f.ty {
-            Type::Path(p) if is_string_path(&p.path) => quote! {
-                #name: rust_code_obfuscator::crypto::decrypt_string(
-                    &self.#name.0, &self.#name.1,
-                    &rust_code_obfuscator::crypto::default_key()
-                ).expect("decryption failed")
-            },
+            Type::Path(p) if is_string_path(&p.path) => open_string(name, field_key, field_aad),
             Type::Path(_) => {
                 let ty = &f.ty;
                 quote! {
-                    #name: rust_code_obfuscator::crypto::decrypt_parse::<#ty>(
-                        &self.#name.0, &self.#name.1,
-                        &rust_code_obfuscator::crypto::default_key()
-                    ).expect("decryption failed")
+                    #name: rust_code_obfuscator::crypto::open_scalar::<#cipher, #ty>(
+                        &self.#name,
+                        #field_aad,
+                        &#field_key
+                    )?
                 }
             }
-            _ => quote! {
-                #name: rust_code_obfuscator::crypto::decrypt_string(
-                    &self.#name.0, &self.#name.1,
-                    &rust_code_obfuscator::crypto::default_key()
-                ).expect("decryption failed")
-            },
+            _ => open_string(name, field_key, field_aad),
         }
     });
 
@@ -154,16 +239,45 @@ pub fn derive_obfuscate(input: TokenStream) -> TokenStream {
         }
 
         impl #obf_name {
+            #[allow(clippy::too_many_arguments)]
             pub fn new_clear(#(#clear_args),*) -> Self {
-                Self {
-                    #(#clear_encrypt),*
-                }
+                Self::new_clear_with(
+                    &rust_code_obfuscator::key_provider::BuildKeyProvider,
+                    #(#clear_arg_names),*
+                )
+                .expect("encryption failed")
             }
 
             pub fn get_clear(&self) -> #name {
-                #name {
+                self.get_clear_with(&rust_code_obfuscator::key_provider::BuildKeyProvider)
+                    .expect("decryption failed")
+            }
+
+            #[allow(clippy::too_many_arguments)]
+            pub fn new_clear_with<P>(
+                provider: &P,
+                #(#clear_args),*
+            ) -> ::core::result::Result<Self, rust_code_obfuscator::errors::ObfuscatorError>
+            where
+                P: rust_code_obfuscator::key_provider::KeyProvider + ?Sized,
+            {
+                let rustfuscator_master = provider.master_key()?;
+                let rustfuscator_key_id = provider.key_id();
+                Ok(Self {
+                    #(#clear_encrypt),*
+                })
+            }
+
+            pub fn get_clear_with<P>(
+                &self,
+                provider: &P,
+            ) -> ::core::result::Result<#name, rust_code_obfuscator::errors::ObfuscatorError>
+            where
+                P: rust_code_obfuscator::key_provider::KeyProvider + ?Sized,
+            {
+                Ok(#name {
                     #(#decrypt_fields),*
-                }
+                })
             }
         }
 
@@ -173,6 +287,474 @@ pub fn derive_obfuscate(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Struct-level `#[obfuscator(...)]` options.
+struct StructOptions {
+    /// `cipher = path::To::Cipher`
+    cipher: Option<Path>,
+    /// `aad = false` stops binding field ciphertexts to the struct/field name.
+    aad: bool,
+    /// `pad` or `pad = N` pads string fields to hide their length.
+    pad: Option<Padding>,
+}
+
+impl StructOptions {
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self {
+            cipher: None,
+            aad: true,
+            pad: None,
+        };
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("obfuscator"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("cipher") {
+                    options.cipher = Some(meta.value()?.parse::<Path>()?);
+                    Ok(())
+                } else if meta.path.is_ident("aad") {
+                    options.aad = meta.value()?.parse::<LitBool>()?.value;
+                    Ok(())
+                } else if meta.path.is_ident("pad") {
+                    options.pad = Some(parse_padding(meta.input)?);
+                    Ok(())
+                } else {
+                    Err(meta.error(
+                        "unsupported obfuscator attribute, expected `cipher = ...`, `aad = ...` or `pad`",
+                    ))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// Parses what follows `pad`: nothing for power-of-two buckets, or `= N` for
+/// multiples of `N`.
+fn parse_padding(input: ParseStream) -> syn::Result<Padding> {
+    if !input.peek(Token![=]) {
+        return Ok(Padding::PowerOfTwo);
+    }
+    input.parse::<Token![=]>()?;
+    let size = input.parse::<LitInt>()?;
+    match size.base10_parse::<usize>()? {
+        0 => Err(syn::Error::new(
+            size.span(),
+            "padding bucket must be at least 1",
+        )),
+        size => Ok(Padding::Bucket(size)),
+    }
+}
+
+fn padding_tokens(padding: Padding) -> proc_macro2::TokenStream {
+    match padding {
+        Padding::PowerOfTwo => quote! { rust_code_obfuscator::crypto::Padding::PowerOfTwo },
+        Padding::Bucket(size) => {
+            quote! { rust_code_obfuscator::crypto::Padding::Bucket(#size) }
+        }
+    }
+}
+
+/// Applies the CLI's source rewrites to a fn, impl block or inline module at
+/// compile time.
+///
+/// `#[obfuscate(strings, flow, nums, flatten)]` picks the rewrites; a bare
+/// `#[obfuscate]` enables all of them:
+///
+/// - `strings`: string literals become `obfuscate_str!` calls.
+/// - `flow`: `obfuscate_flow!()` is injected into `if` / `else` blocks, `match`
+///   arms and loop bodies.
+/// - `nums`: integer literals become `obfuscate_num!` calls.
+/// - `flatten`: fn bodies are rewritten into dispatcher loops over an
+///   obfuscated state variable (control-flow flattening).
+///
+/// Constant contexts and macro arguments are left alone.
+#[proc_macro_attribute]
+pub fn obfuscate(args: TokenStream, input: TokenStream) -> TokenStream {
+    let rewrites = parse_macro_input!(args as transform::Rewrites);
+    let item = parse_macro_input!(input as syn::Item);
+    transform::expand(rewrites, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Routes calls to the named functions through tables of function pointers,
+/// so the compiled code holds no direct call to them.
+///
+/// `#[obfuscate_calls(check_license, keys::decrypt)]` applies to a fn, an impl
+/// block or an inline module and rewrites every plain call to those functions
+/// inside it. Each call site loads its target from a shuffled table mixed with
+/// decoys, at a slot decoded at runtime from an encoded constant; the call
+/// keeps the target's signature, so arguments and results are unchanged.
+///
+/// A target matches calls written with the same path: `keys::decrypt` does
+/// not select `decrypt(..)` or `other::keys::decrypt(..)`, and a bare name
+/// skips calls to a local binding of that name.
+///
+/// Targets must be safe Rust functions. Calls with more than eight arguments
+/// are rejected.
+#[proc_macro_attribute]
+pub fn obfuscate_calls(args: TokenStream, input: TokenStream) -> TokenStream {
+    let targets = parse_macro_input!(args as calls::Targets);
+    let item = parse_macro_input!(input as syn::Item);
+    calls::expand(targets, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Input of the hidden literal macros: the core crate path followed by a
+/// literal and, optionally, `pad` / `pad = N`.
+struct LiteralInput<L> {
+    krate: Path,
+    lit: L,
+    pad: Option<Padding>,
+}
+
+impl<L: Parse> Parse for LiteralInput<L> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Path::parse_mod_style)?;
+        input.parse::<Token![,]>()?;
+        let lit = input.parse()?;
+        let mut pad = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() {
+            let option = input.parse::<Ident>()?;
+            if option != "pad" {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "expected `pad` or `pad = N` after the literal",
+                ));
+            }
+            pad = Some(parse_padding(input)?);
+        }
+        Ok(Self { krate, lit, pad })
+    }
+}
+
+/// Input of `__obfuscated_map!`: the core crate path followed by a braced
+/// list of `"key" => value` entries.
+struct MapInput {
+    krate: Path,
+    entries: syn::punctuated::Punctuated<MapEntry, Token![,]>,
+}
+
+struct MapEntry {
+    key: LitStr,
+    value: syn::Expr,
+}
+
+impl Parse for MapInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Path::parse_mod_style)?;
+        input.parse::<Token![,]>()?;
+        let body;
+        syn::braced!(body in input);
+        let entries = body.parse_terminated(MapEntry::parse, Token![,])?;
+        Ok(Self { krate, entries })
+    }
+}
+
+impl Parse for MapEntry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let value = input.parse()?;
+        Ok(Self { key, value })
+    }
+}
+
+/// Seals a string literal at compile time and expands to an expression that
+/// decrypts it into a `String`.
+///
+/// Used by `obfuscate_string!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_literal(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitStr>);
+    match pad {
+        Some(padding) => {
+            let open = quote! { #krate::obfuscator::__open_literal_padded };
+            let padded = literal::pad(lit.value().as_bytes(), padding);
+            open_sealed_literal(open, &padded, lit.span()).into()
+        }
+        None => {
+            let open = quote! { #krate::obfuscator::__open_literal };
+            open_sealed_literal(open, lit.value().as_bytes(), lit.span()).into()
+        }
+    }
+}
+
+/// Seals a byte string literal at compile time and expands to an expression
+/// that decrypts it into a `Vec<u8>`.
+///
+/// Used by `obfuscate_bytes!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_bytes(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitByteStr>);
+    if pad.is_some() {
+        return syn::Error::new(lit.span(), "obfuscate_bytes! does not support padding")
+            .to_compile_error()
+            .into();
+    }
+    let open = quote! { #krate::obfuscator::__open_literal_bytes };
+    open_sealed_literal(open, &lit.value(), lit.span()).into()
+}
+
+/// Seals a string literal at compile time and expands to a
+/// `__ScopedLiteral` that decrypts it into a stack buffer on demand.
+///
+/// Used by `with_obfuscated!` and `obfuscate_string!`; not meant to be called
+/// directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_scoped(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitStr>);
+    let value = lit.value();
+    match pad {
+        Some(padding) => {
+            let padded = literal::pad(value.as_bytes(), padding);
+            let len = padded.len();
+            let open = quote! { #krate::obfuscator::__ScopedLiteral::<#len>::new_padded };
+            open_sealed_literal(open, &padded, lit.span()).into()
+        }
+        None => {
+            let len = value.len();
+            let open = quote! { #krate::obfuscator::__ScopedLiteral::<#len>::new };
+            open_sealed_literal(open, value.as_bytes(), lit.span()).into()
+        }
+    }
+}
+
+/// Hashes a string literal at compile time under a fresh key and expands to a
+/// `__LiteralDigest` that compares other strings with it in constant time.
+///
+/// Used by `obfuscate_eq!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_digest(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitStr>);
+    if pad.is_some() {
+        return syn::Error::new(lit.span(), "obfuscate_eq! does not support padding")
+            .to_compile_error()
+            .into();
+    }
+    let digest = literal::digest(lit.span(), lit.value().as_bytes());
+    let key = LitByteStr::new(&digest.key, lit.span());
+    let digest = LitByteStr::new(&digest.digest, lit.span());
+    quote! { #krate::obfuscator::__LiteralDigest::new(#key, #digest) }.into()
+}
+
+/// Hashes the keys of a `"key" => value` list under one fresh key and expands
+/// to an `ObfMap` that stores only the hashes.
+///
+/// Used by `obfuscate_map!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_map(input: TokenStream) -> TokenStream {
+    let MapInput { krate, entries } = parse_macro_input!(input as MapInput);
+
+    let mut seen = std::collections::BTreeSet::new();
+    for entry in &entries {
+        if !seen.insert(entry.key.value()) {
+            return syn::Error::new(entry.key.span(), "duplicate key in obfuscate_map!")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let keys: Vec<String> = entries.iter().map(|entry| entry.key.value()).collect();
+    let key_bytes: Vec<&[u8]> = keys.iter().map(|key| key.as_bytes()).collect();
+    let span = entries
+        .first()
+        .map_or_else(proc_macro2::Span::call_site, |entry| entry.key.span());
+    let (key, digests) = literal::digest_all(span, &key_bytes);
+
+    let key = LitByteStr::new(&key, proc_macro2::Span::call_site());
+    let entries = entries.iter().zip(&digests).map(|(entry, digest)| {
+        let digest = LitByteStr::new(digest, entry.key.span());
+        let value = &entry.value;
+        quote! { (#digest, #value) }
+    });
+
+    quote! { #krate::obfuscator::ObfMap::__new(#key, [#(#entries),*]) }.into()
+}
+
+/// Encodes a numeric or char literal at compile time and expands to an
+/// expression that decodes it, typed like the literal.
+///
+/// Used by `obfuscate_num!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_num(input: TokenStream) -> TokenStream {
+    num::expand(parse_macro_input!(input as num::NumInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Rewrites integer arithmetic and bitwise operators into mixed
+/// boolean-arithmetic forms chosen at random for this call site.
+///
+/// Used by `obfuscate_expr!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_expr(input: TokenStream) -> TokenStream {
+    mba::expand(parse_macro_input!(input as mba::ExprInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expands to a dead branch behind a randomly chosen opaque predicate.
+///
+/// Used by `obfuscate_dummy_branch!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_dummy_branch(input: TokenStream) -> TokenStream {
+    if !input.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "obfuscate_dummy_branch! does not accept arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+    opaque::expand().into()
+}
+
+/// Reads a file relative to the calling crate's manifest directory, seals it
+/// in chunks and expands to an expression that decrypts it into a `String`.
+///
+/// Used by `obfuscate_include_str!`; not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_include_str(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitStr>);
+    let open = quote! { #krate::obfuscator::__open_included_str };
+    include_sealed_file(open, &lit, pad, true)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Byte counterpart of [`__obfuscated_include_str`], used by
+/// `obfuscate_include_bytes!`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_include_bytes(input: TokenStream) -> TokenStream {
+    let LiteralInput { krate, lit, pad } = parse_macro_input!(input as LiteralInput<LitStr>);
+    let open = quote! { #krate::obfuscator::__open_included_bytes };
+    include_sealed_file(open, &lit, pad, false)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Expands to the size in bytes of a file named relative to the calling
+/// crate's manifest directory.
+///
+/// Used by the `registry` feature to record the length of included files;
+/// not meant to be called directly.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __obfuscated_include_len(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    let full_path = manifest_relative(&path);
+    match std::fs::metadata(&full_path) {
+        Ok(metadata) => {
+            let len = metadata.len() as usize;
+            quote! { #len }.into()
+        }
+        Err(err) => syn::Error::new(
+            path.span(),
+            format!("cannot read {}: {}", full_path.display(), err),
+        )
+        .to_compile_error()
+        .into(),
+    }
+}
+
+fn manifest_relative(path: &LitStr) -> std::path::PathBuf {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    std::path::Path::new(&manifest_dir).join(path.value())
+}
+
+/// Reads and seals the file named by `path`. The expansion also references
+/// the file through `include_bytes!` in an unused constant, so editing it
+/// triggers a rebuild without embedding its contents.
+fn include_sealed_file(
+    open: proc_macro2::TokenStream,
+    path: &LitStr,
+    pad: Option<Padding>,
+    utf8: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if pad.is_some() {
+        return Err(syn::Error::new(
+            path.span(),
+            "included files do not support padding",
+        ));
+    }
+
+    let full_path = manifest_relative(path);
+    let contents = std::fs::read(&full_path).map_err(|err| {
+        syn::Error::new(
+            path.span(),
+            format!("cannot read {}: {}", full_path.display(), err),
+        )
+    })?;
+    if utf8 && std::str::from_utf8(&contents).is_err() {
+        return Err(syn::Error::new(
+            path.span(),
+            format!("{} is not valid UTF-8", full_path.display()),
+        ));
+    }
+
+    let span = path.span();
+    let sealed = literal::seal_chunked(span, &contents);
+    let chunks = sealed.chunks.iter().map(|(ciphertext, nonce)| {
+        let ciphertext = LitByteStr::new(ciphertext, span);
+        let nonce = LitByteStr::new(nonce, span);
+        quote! { (#ciphertext, #nonce) }
+    });
+    let len = contents.len();
+    let share_a = LitByteStr::new(&sealed.share_a, span);
+    let share_b = LitByteStr::new(&sealed.share_b, span);
+    let call_site = sealed.call_site;
+    let tracked = LitStr::new(&full_path.to_string_lossy(), span);
+
+    Ok(quote! {{
+        const _: &[u8] = ::core::include_bytes!(#tracked);
+        #open(
+            &[#(#chunks),*],
+            #len,
+            #share_a,
+            #share_b,
+            #call_site,
+        )
+    }})
+}
+
+/// Seals `plaintext` and calls `open` with the sealed parts.
+fn open_sealed_literal(
+    open: proc_macro2::TokenStream,
+    plaintext: &[u8],
+    span: proc_macro2::Span,
+) -> proc_macro2::TokenStream {
+    let sealed = literal::seal(span, plaintext);
+
+    let ciphertext = LitByteStr::new(&sealed.ciphertext, span);
+    let nonce = LitByteStr::new(&sealed.nonce, span);
+    let share_a = LitByteStr::new(&sealed.share_a, span);
+    let share_b = LitByteStr::new(&sealed.share_b, span);
+    let call_site = sealed.call_site;
+
+    quote! {
+        #open(
+            #ciphertext,
+            #nonce,
+            #share_a,
+            #share_b,
+            #call_site,
+        )
+    }
+}
+
 fn is_supported_field_type(ty: &Type) -> bool {
     match ty {
         Type::Path(path) if is_string_path(&path.path) => true,
@@ -205,7 +787,7 @@ mod tests {
     use syn::parse_quote;
 
     #[test]
-    fn supports_string_bool_and_all_integer_primitives() {
+    fn supports_string_bool_char_and_all_numeric_primitives() {
         let supported: &[Type] = &[
             parse_quote!(String),
             parse_quote!(std::string::String),
@@ -223,6 +805,9 @@ mod tests {
             parse_quote!(i64),
             parse_quote!(i128),
             parse_quote!(isize),
+            parse_quote!(f32),
+            parse_quote!(f64),
+            parse_quote!(char),
         ];
 
         for ty in supported {
@@ -231,12 +816,161 @@ mod tests {
     }
 
     #[test]
-    fn rejects_containers_floats_and_type_alias_like_paths() {
+    fn reads_cipher_from_obfuscator_attribute() {
+        let input: DeriveInput = parse_quote! {
+            #[obfuscator(cipher = rust_code_obfuscator::crypto::ChaCha20Poly1305)]
+            struct Sealed {
+                value: u32,
+            }
+        };
+
+        let options = StructOptions::from_attrs(&input.attrs).unwrap();
+        let cipher = options.cipher.unwrap();
+        assert!(cipher.segments.last().unwrap().ident == "ChaCha20Poly1305");
+        assert!(options.aad);
+    }
+
+    #[test]
+    fn reads_aad_opt_out_from_obfuscator_attribute() {
+        let input: DeriveInput = parse_quote! {
+            #[obfuscator(aad = false)]
+            struct Sealed {
+                value: u32,
+            }
+        };
+
+        let options = StructOptions::from_attrs(&input.attrs).unwrap();
+        assert!(options.cipher.is_none());
+        assert!(!options.aad);
+    }
+
+    #[test]
+    fn reads_padding_from_obfuscator_attribute() {
+        let power_of_two: DeriveInput = parse_quote! {
+            #[obfuscator(pad)]
+            struct Sealed {
+                value: String,
+            }
+        };
+        let bucket: DeriveInput = parse_quote! {
+            #[obfuscator(aad = false, pad = 64)]
+            struct Sealed {
+                value: String,
+            }
+        };
+        let empty_bucket: DeriveInput = parse_quote! {
+            #[obfuscator(pad = 0)]
+            struct Sealed {
+                value: String,
+            }
+        };
+
+        assert_eq!(
+            StructOptions::from_attrs(&power_of_two.attrs).unwrap().pad,
+            Some(Padding::PowerOfTwo)
+        );
+        assert_eq!(
+            StructOptions::from_attrs(&bucket.attrs).unwrap().pad,
+            Some(Padding::Bucket(64))
+        );
+        assert!(StructOptions::from_attrs(&empty_bucket.attrs).is_err());
+    }
+
+    #[test]
+    fn pads_literals_like_the_core_crate() {
+        let padded = literal::pad(b"flag", Padding::Bucket(16));
+
+        assert_eq!(padded.len(), 16);
+        assert_eq!(&padded[..8], b"\x04\0\0\0flag");
+        assert_eq!(literal::pad(b"abcde", Padding::PowerOfTwo).len(), 16);
+    }
+
+    #[test]
+    fn seals_included_files_in_chunks() {
+        let contents = vec![7u8; literal::CHUNK_LEN * 2 + 1];
+
+        let span = proc_macro2::Span::call_site();
+        let sealed = literal::seal_chunked(span, &contents);
+        let empty = literal::seal_chunked(span, b"");
+
+        assert_eq!(sealed.chunks.len(), 3);
+        assert_eq!(sealed.chunks[2].0.len(), 1 + 16);
+        assert_ne!(sealed.chunks[0].1, sealed.chunks[1].1);
+        assert_eq!(empty.chunks.len(), 1);
+    }
+
+    #[test]
+    fn encodes_numbers_without_the_plain_value() {
+        let expanded = num::expand(parse_quote!(krate, 987654321987u64))
+            .unwrap()
+            .to_string();
+
+        assert!(!expanded.contains("987654321987"));
+        assert!(expanded.contains("black_box"));
+        assert!(expanded.contains("0u64"));
+    }
+
+    #[test]
+    fn number_encoding_is_invertible() {
+        for k in [1u128, 3, 0xdead_beef, u128::MAX] {
+            assert_eq!(k.wrapping_mul(num::mul_inverse(k)), 1);
+        }
+
+        let (bits, _) = num::literal_bits(true, &parse_quote!(42i8)).unwrap();
+        assert_eq!(bits as i8, -42);
+        let (bits, _) = num::literal_bits(true, &parse_quote!(0.5)).unwrap();
+        assert_eq!(f64::from_bits(bits as u64), -0.5);
+        assert_eq!(f32::from_bits((bits >> 64) as u32), -0.5);
+    }
+
+    #[test]
+    fn rejects_out_of_range_suffixed_numbers() {
+        assert!(num::literal_bits(false, &parse_quote!(256u8)).is_err());
+        assert!(num::literal_bits(true, &parse_quote!(1u32)).is_err());
+        assert!(num::literal_bits(true, &parse_quote!(129i8)).is_err());
+        assert!(num::literal_bits(true, &parse_quote!(128i8)).is_ok());
+        assert!(num::literal_bits(false, &parse_quote!("text")).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_obfuscator_attribute_keys() {
+        let input: DeriveInput = parse_quote! {
+            #[obfuscator(algorithm = "aes")]
+            struct Sealed {
+                value: u32,
+            }
+        };
+
+        assert!(StructOptions::from_attrs(&input.attrs).is_err());
+    }
+
+    #[test]
+    fn flatten_keeps_lint_allowances_on_the_dispatchers() {
+        let expanded = transform::expand(
+            parse_quote!(flatten),
+            parse_quote! {
+                fn check(n: i32) -> i32 {
+                    if n < 0 {
+                        return 0;
+                    }
+                    println!("{n}");
+                    n
+                }
+            },
+        )
+        .unwrap()
+        .to_string();
+
+        assert!(expanded.starts_with("fn check"), "{expanded}");
+        assert!(expanded.contains("# [allow (unreachable_code)] {"));
+    }
+
+    #[test]
+    fn rejects_containers_and_type_alias_like_paths() {
         let unsupported: &[Type] = &[
             parse_quote!(Vec<String>),
             parse_quote!(Option<u32>),
-            parse_quote!(f32),
-            parse_quote!(f64),
+            parse_quote!(core::primitive::f64),
             parse_quote!(crate::MyType),
         ];
 
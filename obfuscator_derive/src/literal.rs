@@ -0,0 +1,262 @@
+//! Compile-time sealing for the literal macros of `rust_code_obfuscator_core`.
+//!
+//! Proc macros cannot see the build-time key shares of the core crate, so every
+//! expansion draws its own master key and stores it as two XOR shares next to
+//! the ciphertext. The literal itself is sealed under a subkey of that master.
+//! The key schedule below mirrors `crypto::derive_subkey` with
+//! `KeyContext::call_site`; both sides must change together.
+//!
+//! Included files are sealed in chunks of [`CHUNK_LEN`] bytes, each under
+//! its own nonce and bound to its position, so they can be decrypted one
+//! chunk at a time.
+//!
+//! With `OBFUSCATOR_SEED` set, the randomness for each expansion is derived
+//! from the seed, the crate being compiled, the file, line and column of the
+//! call site and the literal, so rebuilding the same source yields the same
+//! bytes whatever order the macros expand in. Expansions that share a location,
+//! such as several produced by one `macro_rules!` arm, get the same bytes.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use hkdf::hmac::{Hmac, Mac};
+use hkdf::Hkdf;
+use proc_macro2::Span;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KDF_SALT: &[u8] = b"rustfuscator/kdf/v1";
+
+/// Plaintext bytes per chunk of an included file.
+pub(crate) const CHUNK_LEN: usize = 16 * 1024;
+
+pub(crate) struct SealedLiteral {
+    pub ciphertext: Vec<u8>,
+    pub nonce: [u8; NONCE_LEN],
+    pub share_a: [u8; KEY_LEN],
+    pub share_b: [u8; KEY_LEN],
+    pub call_site: u64,
+}
+
+pub(crate) struct SealedChunks {
+    pub chunks: Vec<(Vec<u8>, [u8; NONCE_LEN])>,
+    pub share_a: [u8; KEY_LEN],
+    pub share_b: [u8; KEY_LEN],
+    pub call_site: u64,
+}
+
+pub(crate) fn seal(span: Span, plaintext: &[u8]) -> SealedLiteral {
+    let mut rng = literal_rng(span, plaintext);
+    let (subkey, share_a, share_b, call_site) = call_site_key(&mut rng);
+    let nonce: [u8; NONCE_LEN] = rng.random();
+    let ciphertext = encrypt(&subkey, &nonce, plaintext, &[]);
+
+    SealedLiteral {
+        ciphertext,
+        nonce,
+        share_a,
+        share_b,
+        call_site,
+    }
+}
+
+/// Per-call-site HMAC key and HMAC-SHA256 of a literal. Mirrors
+/// `obfuscator::__LiteralDigest::matches` in the core crate.
+pub(crate) struct LiteralDigest {
+    pub key: [u8; KEY_LEN],
+    pub digest: [u8; 32],
+}
+
+pub(crate) fn digest(span: Span, plaintext: &[u8]) -> LiteralDigest {
+    let mut rng = literal_rng(span, plaintext);
+    let key: [u8; KEY_LEN] = rng.random();
+
+    LiteralDigest {
+        key,
+        digest: keyed_hash(&key, plaintext),
+    }
+}
+
+/// Hashes several literals under one shared key, for `obfuscate_map!` keys.
+pub(crate) fn digest_all(span: Span, literals: &[&[u8]]) -> ([u8; KEY_LEN], Vec<[u8; 32]>) {
+    let mut framed = Vec::new();
+    for literal in literals {
+        framed.extend_from_slice(&(literal.len() as u64).to_le_bytes());
+        framed.extend_from_slice(literal);
+    }
+    let mut rng = literal_rng(span, &framed);
+    let key: [u8; KEY_LEN] = rng.random();
+    let digests = literals
+        .iter()
+        .map(|literal| keyed_hash(&key, literal))
+        .collect();
+    (key, digests)
+}
+
+fn keyed_hash(key: &[u8; KEY_LEN], input: &[u8]) -> [u8; 32] {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(input);
+    mac.finalize().into_bytes().into()
+}
+
+/// Seals `plaintext` in chunks of [`CHUNK_LEN`] bytes. Empty input still
+/// yields one (empty) chunk.
+pub(crate) fn seal_chunked(span: Span, plaintext: &[u8]) -> SealedChunks {
+    let mut rng = literal_rng(span, plaintext);
+    let (subkey, share_a, share_b, call_site) = call_site_key(&mut rng);
+
+    let count = plaintext.len().div_ceil(CHUNK_LEN).max(1);
+    let chunks = (0..count)
+        .map(|index| {
+            let start = index * CHUNK_LEN;
+            let end = (start + CHUNK_LEN).min(plaintext.len());
+            let nonce: [u8; NONCE_LEN] = rng.random();
+            let aad = chunk_aad(index as u32, index + 1 == count);
+            (
+                encrypt(&subkey, &nonce, &plaintext[start..end], &aad),
+                nonce,
+            )
+        })
+        .collect();
+
+    SealedChunks {
+        chunks,
+        share_a,
+        share_b,
+        call_site,
+    }
+}
+
+/// Mirrors `obfuscator::__included_chunk_aad`: `index: u32 LE | last: u8`.
+fn chunk_aad(index: u32, last: bool) -> [u8; 5] {
+    let mut aad = [0u8; 5];
+    aad[..4].copy_from_slice(&index.to_le_bytes());
+    aad[4] = last as u8;
+    aad
+}
+
+/// Draws a master key for one call site, returning the subkey the value is
+/// sealed under and the XOR shares of the master.
+fn call_site_key(rng: &mut StdRng) -> ([u8; KEY_LEN], [u8; KEY_LEN], [u8; KEY_LEN], u64) {
+    let master: [u8; KEY_LEN] = rng.random();
+    let share_a: [u8; KEY_LEN] = rng.random();
+    let share_b = core::array::from_fn(|i| master[i] ^ share_a[i]);
+    let call_site: u64 = rng.random();
+    (
+        derive_subkey(&master, call_site),
+        share_a,
+        share_b,
+        call_site,
+    )
+}
+
+/// Mirrors `crypto::Padding` in the core crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Padding {
+    PowerOfTwo,
+    Bucket(usize),
+}
+
+/// Mirrors `crypto::pad`: `len: u32 LE | plaintext | zero bytes`.
+pub(crate) fn pad(plaintext: &[u8], padding: Padding) -> Vec<u8> {
+    let framed = 4 + plaintext.len();
+    let padded_len = match padding {
+        Padding::PowerOfTwo => framed.next_power_of_two(),
+        Padding::Bucket(size) => framed.div_ceil(size) * size,
+    };
+
+    let mut padded = Vec::with_capacity(padded_len);
+    padded.extend_from_slice(&(plaintext.len() as u32).to_le_bytes());
+    padded.extend_from_slice(plaintext);
+    padded.resize(padded_len, 0);
+    padded
+}
+
+/// Randomness for the expansion at `span`, seeded by `OBFUSCATOR_SEED` when
+/// set. `context` tells apart expansions at the same location.
+pub(crate) fn literal_rng(span: Span, context: &[u8]) -> StdRng {
+    let Some(seed) = std::env::var("OBFUSCATOR_SEED")
+        .ok()
+        .filter(|seed| !seed.is_empty())
+    else {
+        return StdRng::from_os_rng();
+    };
+
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    StdRng::from_seed(seeded_bytes(
+        &seed,
+        "literal",
+        &[crate_name.as_bytes(), location(span).as_bytes(), context],
+    ))
+}
+
+/// `file:line:column` of `span`, with the file relative to the crate root.
+/// Empty outside a proc macro, e.g. in unit tests.
+fn location(span: Span) -> String {
+    if !proc_macro::is_available() {
+        return String::new();
+    }
+    let span = span.unwrap();
+    let file = span.file();
+    // Crates from a registry are compiled from absolute paths, which differ
+    // between machines.
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = match Path::new(&file).strip_prefix(&manifest_dir) {
+        Ok(relative) if !manifest_dir.is_empty() => relative.to_string_lossy().into_owned(),
+        _ => file,
+    };
+    format!("{file}:{}:{}", span.line(), span.column())
+}
+
+/// Mirrors `utils::seeded_bytes` in the core crate, with the context given in parts.
+fn seeded_bytes(seed: &str, domain: &str, context: &[&[u8]]) -> [u8; 32] {
+    let mut context_bytes = Vec::new();
+    for part in context {
+        context_bytes.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        context_bytes.extend_from_slice(part);
+    }
+
+    let mut hasher = Sha256::new();
+    for part in [
+        b"rustfuscator/seed/v1".as_slice(),
+        seed.as_bytes(),
+        domain.as_bytes(),
+        &context_bytes,
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// HKDF-SHA256 over the same `info` layout as `KeyContext::call_site`:
+/// three empty length-prefixed strings followed by the call-site id.
+fn derive_subkey(master: &[u8; KEY_LEN], call_site: u64) -> [u8; KEY_LEN] {
+    let mut info = Vec::with_capacity(3 * 4 + 8);
+    for _ in 0..3 {
+        info.extend_from_slice(&0u32.to_le_bytes());
+    }
+    info.extend_from_slice(&call_site.to_le_bytes());
+
+    let mut subkey = [0u8; KEY_LEN];
+    Hkdf::<Sha256>::new(Some(KDF_SALT), master)
+        .expand(&info, &mut subkey)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    subkey
+}
+
+#[cfg(not(feature = "chacha20poly1305_default"))]
+fn encrypt(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], msg: &[u8], aad: &[u8]) -> Vec<u8> {
+    aes_gcm::Aes256Gcm::new(key.into())
+        .encrypt(nonce.into(), Payload { msg, aad })
+        .expect("AES-256-GCM encryption of a literal failed")
+}
+
+#[cfg(feature = "chacha20poly1305_default")]
+fn encrypt(key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN], msg: &[u8], aad: &[u8]) -> Vec<u8> {
+    chacha20poly1305::ChaCha20Poly1305::new(key.into())
+        .encrypt(nonce.into(), Payload { msg, aad })
+        .expect("ChaCha20-Poly1305 encryption of a literal failed")
+}
@@ -0,0 +1,365 @@
+//! Mixed boolean-arithmetic rewriting for `obfuscate_expr!`.
+//!
+//! The expression is lowered to one local per operation, operands first and
+//! left to right, so every operand is still evaluated once and in order.
+//! Each `+ - * & | ^ ! -` is then expanded through a randomly chosen identity
+//! that holds modulo 2^n, such as `x + y == (x ^ y) + 2 * (x & y)`, and the
+//! operations the identity uses are expanded again until `depth` runs out.
+//! The remaining primitives go through the core `__Mba` trait, which uses
+//! wrapping arithmetic, and some intermediate values pass through
+//! `black_box` so the optimiser cannot match the identities back.
+//!
+//! Other operators (division, shifts, comparisons, ...) are kept as written,
+//! with their operands rewritten; anything else is an opaque operand.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use rand::{rngs::StdRng, Rng};
+use syn::{
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    BinOp, Expr, Ident, LitInt, Path, Token, UnOp,
+};
+
+use crate::literal;
+
+const DEFAULT_DEPTH: u32 = 2;
+const MAX_DEPTH: u32 = 4;
+
+/// `$crate, expr` or `$crate, expr, depth = N`.
+pub(crate) struct ExprInput {
+    krate: Path,
+    expr: Expr,
+    depth: u32,
+}
+
+impl Parse for ExprInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Path::parse_mod_style)?;
+        input.parse::<Token![,]>()?;
+        let expr = input.parse()?;
+        let mut depth = DEFAULT_DEPTH;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let option = input.parse::<Ident>()?;
+            if option != "depth" {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "expected `depth = N` after the expression",
+                ));
+            }
+            input.parse::<Token![=]>()?;
+            let lit = input.parse::<LitInt>()?;
+            depth = lit.base10_parse()?;
+            if !(1..=MAX_DEPTH).contains(&depth) {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    format!("depth must be between 1 and {MAX_DEPTH}"),
+                ));
+            }
+        }
+        Ok(Self { krate, expr, depth })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    And,
+    Or,
+    Xor,
+}
+
+/// Expands `obfuscate_expr!` for one call site.
+pub(crate) fn expand(input: ExprInput) -> syn::Result<TokenStream> {
+    let ExprInput { krate, expr, depth } = input;
+    if !is_rewritable(&expr) {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "obfuscate_expr! expects integer arithmetic or bitwise operators",
+        ));
+    }
+
+    let mut lowering = Lowering {
+        rng: literal::literal_rng(expr.span(), quote! { #expr }.to_string().as_bytes()),
+        krate,
+        stmts: Vec::new(),
+        next: 0,
+    };
+    let result = lowering.lower(&expr, depth);
+    let stmts = lowering.stmts;
+    Ok(quote! {{
+        #(#stmts)*
+        #result
+    }})
+}
+
+fn is_rewritable(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(node) => binary_op(&node.op).is_some(),
+        Expr::Unary(node) => !is_negative_literal(expr) && !matches!(node.op, UnOp::Deref(_)),
+        Expr::Paren(node) => is_rewritable(&node.expr),
+        Expr::Group(node) => is_rewritable(&node.expr),
+        _ => false,
+    }
+}
+
+fn binary_op(op: &BinOp) -> Option<Op> {
+    Some(match op {
+        BinOp::Add(_) => Op::Add,
+        BinOp::Sub(_) => Op::Sub,
+        BinOp::Mul(_) => Op::Mul,
+        BinOp::BitAnd(_) => Op::And,
+        BinOp::BitOr(_) => Op::Or,
+        BinOp::BitXor(_) => Op::Xor,
+        _ => return None,
+    })
+}
+
+fn is_kept(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Div(_)
+            | BinOp::Rem(_)
+            | BinOp::Shl(_)
+            | BinOp::Shr(_)
+            | BinOp::Eq(_)
+            | BinOp::Ne(_)
+            | BinOp::Lt(_)
+            | BinOp::Le(_)
+            | BinOp::Gt(_)
+            | BinOp::Ge(_)
+    )
+}
+
+/// `-128i8` has to stay one operand, it would overflow as `-(128i8)`.
+fn is_negative_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Unary(node) => matches!(node.op, UnOp::Neg(_)) && matches!(&*node.expr, Expr::Lit(_)),
+        _ => false,
+    }
+}
+
+struct Lowering {
+    rng: StdRng,
+    krate: Path,
+    stmts: Vec<TokenStream>,
+    next: usize,
+}
+
+impl Lowering {
+    fn bind(&mut self, value: TokenStream) -> Ident {
+        let var = format_ident!("rustfuscator_mba_{}", self.next);
+        self.next += 1;
+        self.stmts.push(quote! { let #var = #value; });
+        var
+    }
+
+    fn lower(&mut self, expr: &Expr, depth: u32) -> Ident {
+        match expr {
+            Expr::Paren(node) => self.lower(&node.expr, depth),
+            Expr::Group(node) => self.lower(&node.expr, depth),
+            Expr::Binary(node) if binary_op(&node.op).is_some() => {
+                let x = self.lower(&node.left, depth);
+                let y = self.lower(&node.right, depth);
+                let op = binary_op(&node.op).expect("checked above");
+                self.binary(op, &x, &y, depth)
+            }
+            // Division, shifts and comparisons keep their own semantics. `&&` /
+            // `||` short-circuit and assignments need a place, so those are
+            // operands.
+            Expr::Binary(node) if is_kept(&node.op) => {
+                let x = self.lower(&node.left, depth);
+                let y = self.lower(&node.right, depth);
+                let op = &node.op;
+                self.bind(quote! { #x #op #y })
+            }
+            Expr::Unary(node) if !is_negative_literal(expr) => match node.op {
+                UnOp::Not(_) => {
+                    let x = self.lower(&node.expr, depth);
+                    self.not(&x, depth)
+                }
+                UnOp::Neg(_) => {
+                    let x = self.lower(&node.expr, depth);
+                    self.neg(&x, depth)
+                }
+                _ => self.bind(quote! { #expr }),
+            },
+            _ => self.bind(quote! { #expr }),
+        }
+    }
+
+    fn primitive(&mut self, method: &str, args: &[&Ident]) -> Ident {
+        let krate = &self.krate;
+        let method = format_ident!("__{}", method);
+        let value = quote! { #krate::obfuscator::__Mba::#method(#(#args),*) };
+        // An opaque value here and there keeps the identities from being
+        // recognised and folded back by the optimiser.
+        if self.rng.random_ratio(1, 4) {
+            self.bind(quote! { ::core::hint::black_box(#value) })
+        } else {
+            self.bind(value)
+        }
+    }
+
+    fn one(&mut self, like: &Ident) -> Ident {
+        self.primitive("one", &[like])
+    }
+
+    fn twice(&mut self, x: &Ident, depth: u32) -> Ident {
+        self.binary(Op::Add, x, x, depth)
+    }
+
+    fn binary(&mut self, op: Op, x: &Ident, y: &Ident, depth: u32) -> Ident {
+        if depth == 0 {
+            let method = match op {
+                Op::Add => "add",
+                Op::Sub => "sub",
+                Op::Mul => "mul",
+                Op::And => "and",
+                Op::Or => "or",
+                Op::Xor => "xor",
+            };
+            return self.primitive(method, &[x, y]);
+        }
+
+        let d = depth - 1;
+        match (op, self.rng.random_range(0..3)) {
+            // (x ^ y) + 2(x & y)
+            (Op::Add, 0) => {
+                let xor = self.binary(Op::Xor, x, y, d);
+                let and = self.binary(Op::And, x, y, d);
+                let and2 = self.twice(&and, d);
+                self.binary(Op::Add, &xor, &and2, d)
+            }
+            // (x | y) + (x & y)
+            (Op::Add, 1) => {
+                let or = self.binary(Op::Or, x, y, d);
+                let and = self.binary(Op::And, x, y, d);
+                self.binary(Op::Add, &or, &and, d)
+            }
+            // 2(x | y) - (x ^ y)
+            (Op::Add, _) => {
+                let or = self.binary(Op::Or, x, y, d);
+                let or2 = self.twice(&or, d);
+                let xor = self.binary(Op::Xor, x, y, d);
+                self.binary(Op::Sub, &or2, &xor, d)
+            }
+            // (x ^ y) - 2(!x & y)
+            (Op::Sub, 0) => {
+                let xor = self.binary(Op::Xor, x, y, d);
+                let not_x = self.not(x, d);
+                let and = self.binary(Op::And, &not_x, y, d);
+                let and2 = self.twice(&and, d);
+                self.binary(Op::Sub, &xor, &and2, d)
+            }
+            // (x & !y) - (!x & y)
+            (Op::Sub, 1) => {
+                let not_y = self.not(y, d);
+                let left = self.binary(Op::And, x, &not_y, d);
+                let not_x = self.not(x, d);
+                let right = self.binary(Op::And, &not_x, y, d);
+                self.binary(Op::Sub, &left, &right, d)
+            }
+            // x + !y + 1
+            (Op::Sub, _) => {
+                let not_y = self.not(y, d);
+                let sum = self.binary(Op::Add, x, &not_y, d);
+                let one = self.one(x);
+                self.binary(Op::Add, &sum, &one, d)
+            }
+            // (x & y)(x | y) + (x & !y)(!x & y)
+            (Op::Mul, _) => {
+                let and = self.binary(Op::And, x, y, d);
+                let or = self.binary(Op::Or, x, y, d);
+                let left = self.binary(Op::Mul, &and, &or, d);
+                let not_y = self.not(y, d);
+                let x_only = self.binary(Op::And, x, &not_y, d);
+                let not_x = self.not(x, d);
+                let y_only = self.binary(Op::And, &not_x, y, d);
+                let right = self.binary(Op::Mul, &x_only, &y_only, d);
+                self.binary(Op::Add, &left, &right, d)
+            }
+            // (x | y) - (x ^ y)
+            (Op::And, 0) => {
+                let or = self.binary(Op::Or, x, y, d);
+                let xor = self.binary(Op::Xor, x, y, d);
+                self.binary(Op::Sub, &or, &xor, d)
+            }
+            // !(!x | !y)
+            (Op::And, 1) => {
+                let not_x = self.not(x, d);
+                let not_y = self.not(y, d);
+                let or = self.binary(Op::Or, &not_x, &not_y, d);
+                self.not(&or, d)
+            }
+            // (x + y) - (x | y)
+            (Op::And, _) => {
+                let sum = self.binary(Op::Add, x, y, d);
+                let or = self.binary(Op::Or, x, y, d);
+                self.binary(Op::Sub, &sum, &or, d)
+            }
+            // (x ^ y) + (x & y)
+            (Op::Or, 0) => {
+                let xor = self.binary(Op::Xor, x, y, d);
+                let and = self.binary(Op::And, x, y, d);
+                self.binary(Op::Add, &xor, &and, d)
+            }
+            // (x & !y) + y
+            (Op::Or, 1) => {
+                let not_y = self.not(y, d);
+                let x_only = self.binary(Op::And, x, &not_y, d);
+                self.binary(Op::Add, &x_only, y, d)
+            }
+            // !(!x & !y)
+            (Op::Or, _) => {
+                let not_x = self.not(x, d);
+                let not_y = self.not(y, d);
+                let and = self.binary(Op::And, &not_x, &not_y, d);
+                self.not(&and, d)
+            }
+            // (x | y) - (x & y)
+            (Op::Xor, 0) => {
+                let or = self.binary(Op::Or, x, y, d);
+                let and = self.binary(Op::And, x, y, d);
+                self.binary(Op::Sub, &or, &and, d)
+            }
+            // (x + y) - 2(x & y)
+            (Op::Xor, 1) => {
+                let sum = self.binary(Op::Add, x, y, d);
+                let and = self.binary(Op::And, x, y, d);
+                let and2 = self.twice(&and, d);
+                self.binary(Op::Sub, &sum, &and2, d)
+            }
+            // (x & !y) | (!x & y)
+            (Op::Xor, _) => {
+                let not_y = self.not(y, d);
+                let x_only = self.binary(Op::And, x, &not_y, d);
+                let not_x = self.not(x, d);
+                let y_only = self.binary(Op::And, &not_x, y, d);
+                self.binary(Op::Or, &x_only, &y_only, d)
+            }
+        }
+    }
+
+    fn not(&mut self, x: &Ident, depth: u32) -> Ident {
+        if depth == 0 {
+            return self.primitive("not", &[x]);
+        }
+        // !x == -x - 1
+        let neg = self.neg(x, depth - 1);
+        let one = self.one(x);
+        self.binary(Op::Sub, &neg, &one, depth - 1)
+    }
+
+    fn neg(&mut self, x: &Ident, depth: u32) -> Ident {
+        if depth == 0 {
+            return self.primitive("neg", &[x]);
+        }
+        // -x == !x + 1
+        let not = self.not(x, depth - 1);
+        let one = self.one(x);
+        self.binary(Op::Add, &not, &one, depth - 1)
+    }
+}
@@ -0,0 +1,194 @@
+//! Compile-time encoding for `obfuscate_num!`.
+//!
+//! The literal is widened to a `u128` bit pattern (integers sign-extended,
+//! floats as `f64` bits in the low half and `f32` bits above them, chars as
+//! their scalar value) and pushed through a random chain of invertible
+//! wrapping operations. The expansion only contains the encoded constant and
+//! the inverse chain, behind `black_box`, so the value never appears as an
+//! immediate and cannot be constant-folded back.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use rand::{rngs::StdRng, Rng};
+use syn::{
+    parse::{Parse, ParseStream},
+    Lit, LitChar, LitFloat, LitInt, Path, Token,
+};
+
+use crate::literal;
+
+/// `$crate, lit` or `$crate, -lit`.
+pub(crate) struct NumInput {
+    krate: Path,
+    negative: bool,
+    lit: Lit,
+}
+
+impl Parse for NumInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let krate = input.call(Path::parse_mod_style)?;
+        input.parse::<Token![,]>()?;
+        let negative = input.parse::<Option<Token![-]>>()?.is_some();
+        let lit = input.parse()?;
+        Ok(Self {
+            krate,
+            negative,
+            lit,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    Xor(u128),
+    Add(u128),
+    RotateLeft(u32),
+    Mul(u128),
+}
+
+impl Op {
+    fn random(rng: &mut StdRng) -> Self {
+        match rng.random_range(0..4) {
+            0 => Op::Xor(rng.random()),
+            1 => Op::Add(rng.random()),
+            2 => Op::RotateLeft(rng.random_range(1..128)),
+            _ => Op::Mul(rng.random::<u128>() | 1),
+        }
+    }
+
+    pub(crate) fn encode(self, value: u128) -> u128 {
+        match self {
+            Op::Xor(k) => value ^ k,
+            Op::Add(k) => value.wrapping_add(k),
+            Op::RotateLeft(r) => value.rotate_left(r),
+            Op::Mul(k) => value.wrapping_mul(k),
+        }
+    }
+
+    /// The inverse of [`Op::encode`] as an expression over `bits`.
+    fn decode_tokens(self, bits: &TokenStream) -> TokenStream {
+        match self {
+            Op::Xor(k) => quote! { #bits ^ #k },
+            Op::Add(k) => quote! { #bits.wrapping_sub(#k) },
+            Op::RotateLeft(r) => quote! { #bits.rotate_right(#r) },
+            Op::Mul(k) => {
+                let inverse = mul_inverse(k);
+                quote! { #bits.wrapping_mul(#inverse) }
+            }
+        }
+    }
+}
+
+/// Inverse of an odd `k` modulo 2^128, by Newton iteration.
+pub(crate) fn mul_inverse(k: u128) -> u128 {
+    let mut inverse = k;
+    for _ in 0..7 {
+        inverse = inverse.wrapping_mul(2u128.wrapping_sub(k.wrapping_mul(inverse)));
+    }
+    inverse
+}
+
+/// Bit pattern of the literal and an unsuffixed (or identically suffixed) zero
+/// of the same kind, so the decoded value is typed exactly like the literal.
+pub(crate) fn literal_bits(negative: bool, lit: &Lit) -> syn::Result<(u128, TokenStream)> {
+    match lit {
+        Lit::Int(int) => int_bits(negative, int),
+        Lit::Float(float) => float_bits(negative, float),
+        Lit::Char(ch) if !negative => Ok((u128::from(ch.value()), {
+            let zero = LitChar::new('\0', ch.span());
+            quote! { #zero }
+        })),
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "obfuscate_num! only accepts integer, float and char literals",
+        )),
+    }
+}
+
+fn int_bits(negative: bool, int: &LitInt) -> syn::Result<(u128, TokenStream)> {
+    let magnitude = int.base10_parse::<u128>()?;
+    let suffix = int.suffix();
+    if !suffix.is_empty() {
+        check_int_range(negative, magnitude, suffix, int.span())?;
+    }
+
+    let bits = if negative {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    };
+    let zero = LitInt::new(&format!("0{suffix}"), int.span());
+    Ok((bits, quote! { #zero }))
+}
+
+fn check_int_range(negative: bool, magnitude: u128, suffix: &str, span: Span) -> syn::Result<()> {
+    let (min_magnitude, max) = match suffix {
+        "u8" => (0, u8::MAX as u128),
+        "u16" => (0, u16::MAX as u128),
+        "u32" => (0, u32::MAX as u128),
+        "u64" | "usize" => (0, u64::MAX as u128),
+        "u128" => (0, u128::MAX),
+        "i8" => (1 << 7, i8::MAX as u128),
+        "i16" => (1 << 15, i16::MAX as u128),
+        "i32" => (1 << 31, i32::MAX as u128),
+        "i64" | "isize" => (1 << 63, i64::MAX as u128),
+        "i128" => (1 << 127, i128::MAX as u128),
+        _ => return Err(syn::Error::new(span, "unsupported integer suffix")),
+    };
+    let in_range = if negative {
+        magnitude <= min_magnitude
+    } else {
+        magnitude <= max
+    };
+    if in_range {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("literal out of range for `{suffix}`"),
+        ))
+    }
+}
+
+fn float_bits(negative: bool, float: &LitFloat) -> syn::Result<(u128, TokenStream)> {
+    let sign = if negative { -1.0 } else { 1.0 };
+    let wide = sign * float.base10_parse::<f64>()?;
+    let narrow = sign as f32 * float.base10_parse::<f32>()?;
+    let bits = u128::from(wide.to_bits()) | (u128::from(narrow.to_bits()) << 64);
+    let zero = LitFloat::new(&format!("0.0{}", float.suffix()), float.span());
+    Ok((bits, quote! { #zero }))
+}
+
+/// Expands `obfuscate_num!` for one call site.
+pub(crate) fn expand(input: NumInput) -> syn::Result<TokenStream> {
+    let NumInput {
+        krate,
+        negative,
+        lit,
+    } = input;
+    let (bits, zero) = literal_bits(negative, &lit)?;
+
+    let context = format!("num:{}{}", if negative { "-" } else { "" }, quote! { #lit });
+    let mut rng = literal::literal_rng(lit.span(), context.as_bytes());
+    let ops: Vec<Op> = (0..rng.random_range(3..=5))
+        .map(|_| Op::random(&mut rng))
+        .collect();
+    let encoded = ops.iter().fold(bits, |value, op| op.encode(value));
+
+    let var = quote! { rustfuscator_bits };
+    let steps = ops.iter().rev().map(|op| {
+        let step = op.decode_tokens(&var);
+        quote! { let #var: u128 = #step; }
+    });
+
+    Ok(quote! {
+        #krate::obfuscator::__num_from_bits(
+            {
+                let #var: u128 = ::core::hint::black_box(#encoded);
+                #(#steps)*
+                #var
+            },
+            #zero,
+        )
+    })
+}
@@ -0,0 +1,146 @@
+//! Opaque predicates for `obfuscate_dummy_branch!`.
+//!
+//! Every call site gets a randomly chosen predicate that is always false but
+//! cannot be decided without knowing it. Some are number-theoretic identities
+//! on a runtime value, some rely on writes through a `black_box`-ed alias,
+//! and some check runtime properties such as stack alignment. The dead branch
+//! holds one or two randomly parameterised bodies that look like key
+//! schedules, table setup or hashing, so it cannot be told apart by shape.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use rand::{rngs::StdRng, Rng};
+
+use crate::literal;
+
+/// Expands `obfuscate_dummy_branch!` for one call site.
+pub(crate) fn expand() -> TokenStream {
+    let mut rng = literal::literal_rng(Span::call_site(), b"dummy_branch");
+    let x = quote! { rustfuscator_x };
+
+    let salt: u64 = rng.random();
+    let source = if rng.random() {
+        // Depends on where the stack happens to be at runtime.
+        quote! {
+            let rustfuscator_anchor = 0u8;
+            let #x: u64 = ::core::hint::black_box(
+                ::core::ptr::addr_of!(rustfuscator_anchor) as usize as u64,
+            ) ^ #salt;
+        }
+    } else {
+        quote! { let #x: u64 = ::core::hint::black_box(#salt); }
+    };
+
+    let (setup, predicate) = predicate(&mut rng, &x);
+    let bodies = (0..rng.random_range(1..=2)).map(|_| bogus_body(&mut rng, &x));
+
+    quote! {{
+        #source
+        #setup
+        if #predicate {
+            #(#bodies)*
+        }
+    }}
+}
+
+/// An always-false predicate on `x`, with the statements it needs first.
+fn predicate(rng: &mut StdRng, x: &TokenStream) -> (TokenStream, TokenStream) {
+    let key: u64 = rng.random();
+    match rng.random_range(0..8) {
+        // n(n + 1) is even, and wrapping keeps the parity.
+        0 => (
+            quote! {},
+            quote! { #x.wrapping_mul(#x.wrapping_add(1)) & 1 == 1 },
+        ),
+        // Squares are 0 or 1 mod 4, and wrapping keeps the residue.
+        1 => (quote! {}, quote! { #x.wrapping_mul(#x) & 3 >= 2 }),
+        // n^3 - n is divisible by 3; n < 2^32 keeps it within u128.
+        2 => (
+            quote! { let rustfuscator_n = (#x as u32) as u128; },
+            quote! { (rustfuscator_n * rustfuscator_n * rustfuscator_n - rustfuscator_n) % 3 != 0 },
+        ),
+        // n^2 + 1 is never divisible by 3.
+        3 => (
+            quote! { let rustfuscator_n = #x as u128; },
+            quote! { (rustfuscator_n * rustfuscator_n + 1) % 3 == 0 },
+        ),
+        // A write through an opaque alias is visible through the original.
+        4 => (
+            quote! {
+                let rustfuscator_cell = ::core::cell::Cell::new(#x);
+                let rustfuscator_alias = ::core::hint::black_box(&rustfuscator_cell);
+                rustfuscator_alias.set(rustfuscator_alias.get() ^ #key);
+            },
+            quote! { rustfuscator_cell.get() != #x ^ #key },
+        ),
+        5 => (
+            quote! {
+                let mut rustfuscator_slots = [#x, !#x];
+                ::core::hint::black_box(&mut rustfuscator_slots).swap(0, 1);
+            },
+            quote! { rustfuscator_slots[0] != !#x },
+        ),
+        // A `u64` local is always aligned for `u64`.
+        6 => (
+            quote! { let rustfuscator_word = ::core::hint::black_box(#x ^ #key); },
+            quote! {
+                ::core::hint::black_box(::core::ptr::addr_of!(rustfuscator_word)) as usize
+                    % ::core::mem::align_of::<u64>()
+                    != 0
+            },
+        ),
+        // A runtime slice keeps the length it was built with.
+        _ => {
+            let len = rng.random_range(2..=8usize);
+            (
+                quote! { let rustfuscator_buffer = [#x ^ #key; #len]; },
+                quote! { ::core::hint::black_box(&rustfuscator_buffer[..]).len() != #len },
+            )
+        }
+    }
+}
+
+/// Plausible code for the dead branch.
+fn bogus_body(rng: &mut StdRng, x: &TokenStream) -> TokenStream {
+    let key = rng.random::<u64>() | 1;
+    match rng.random_range(0..3) {
+        0 => {
+            let rounds = rng.random_range(4..=16u64);
+            let rotate = rng.random_range(1..64u32);
+            quote! {
+                let mut rustfuscator_acc = #x;
+                for rustfuscator_round in 0..#rounds {
+                    rustfuscator_acc =
+                        rustfuscator_acc.rotate_left(#rotate).wrapping_mul(#key) ^ rustfuscator_round;
+                }
+                ::core::hint::black_box(rustfuscator_acc);
+            }
+        }
+        1 => {
+            let len = rng.random_range(8..=32usize);
+            let mask: u8 = rng.random();
+            quote! {
+                let mut rustfuscator_table = [0u8; #len];
+                for (rustfuscator_i, rustfuscator_byte) in rustfuscator_table.iter_mut().enumerate() {
+                    *rustfuscator_byte = (#x >> ((rustfuscator_i % 8) * 8)) as u8 ^ #mask;
+                }
+                ::core::hint::black_box(&rustfuscator_table);
+            }
+        }
+        _ => {
+            let expected: u64 = rng.random();
+            quote! {
+                let mut rustfuscator_hash = #key;
+                let mut rustfuscator_rest = #x;
+                while rustfuscator_rest != 0 {
+                    rustfuscator_hash = (rustfuscator_hash ^ (rustfuscator_rest & 0xff))
+                        .wrapping_mul(0x0100_0000_01b3);
+                    rustfuscator_rest >>= 8;
+                }
+                if rustfuscator_hash == #expected {
+                    ::core::hint::black_box(rustfuscator_hash);
+                }
+            }
+        }
+    }
+}
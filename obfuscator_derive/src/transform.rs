@@ -0,0 +1,243 @@
+//! `#[obfuscate]`: the source rewrites of the CLI's `ObfuscationTransformer`,
+//! applied to one fn, impl block or inline module at compile time.
+//!
+//! Unlike the CLI, the attribute sees code that already parses, so it can
+//! wrap every string literal in expression position: `obfuscate_str!` yields
+//! the same `&'static str` a literal would. Integer literals go through
+//! `obfuscate_num!`, which infers its type like the literal did. Places that
+//! must stay constant (`const` / `static` items, `const fn` bodies, `const`
+//! blocks, array lengths, types, enum discriminants and attributes) are left
+//! untouched, as are macro arguments. Flattening runs last, on the rewritten
+//! bodies; see [`crate::flatten`].
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::VisitMut,
+    Block, Expr, ExprLit, ExprUnary, Ident, Item, Lit, Stmt, Token, UnOp,
+};
+
+use crate::{flatten::Flattener, literal::literal_rng};
+
+/// Arguments of `#[obfuscate(...)]`. No arguments enables every rewrite.
+pub(crate) struct Rewrites {
+    strings: bool,
+    flow: bool,
+    nums: bool,
+    flatten: bool,
+}
+
+impl Parse for Rewrites {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let names = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+        if names.is_empty() {
+            return Ok(Self {
+                strings: true,
+                flow: true,
+                nums: true,
+                flatten: true,
+            });
+        }
+
+        let mut rewrites = Self {
+            strings: false,
+            flow: false,
+            nums: false,
+            flatten: false,
+        };
+        for name in names {
+            let enabled = match name.to_string().as_str() {
+                "strings" => &mut rewrites.strings,
+                "flow" => &mut rewrites.flow,
+                "nums" => &mut rewrites.nums,
+                "flatten" => &mut rewrites.flatten,
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        "expected `strings`, `flow`, `nums` or `flatten`",
+                    ))
+                }
+            };
+            if *enabled {
+                return Err(syn::Error::new(name.span(), "duplicate rewrite"));
+            }
+            *enabled = true;
+        }
+        Ok(rewrites)
+    }
+}
+
+pub(crate) fn expand(mut rewrites: Rewrites, mut item: Item) -> syn::Result<TokenStream> {
+    match &mut item {
+        Item::Fn(_) | Item::Impl(_) => {}
+        Item::Mod(module) if module.content.is_some() => {}
+        other => {
+            return Err(syn::Error::new_spanned(
+                other,
+                "#[obfuscate] applies to a fn, an impl block or an inline module",
+            ))
+        }
+    }
+
+    rewrites.visit_item_mut(&mut item);
+    if rewrites.flatten {
+        let seed = quote! { #item }.to_string();
+        FlattenFns(Flattener::new(literal_rng(item.span(), seed.as_bytes())))
+            .visit_item_mut(&mut item);
+    }
+    Ok(quote! { #item })
+}
+
+/// Runs the flattener on every fn body in the item, nested fns included.
+struct FlattenFns(Flattener);
+
+impl VisitMut for FlattenFns {
+    fn visit_item_fn_mut(&mut self, node: &mut syn::ItemFn) {
+        self.0.flatten_fn(&node.sig, &mut node.block);
+        self.visit_block_mut(&mut node.block);
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, node: &mut syn::ImplItemFn) {
+        self.0.flatten_fn(&node.sig, &mut node.block);
+        self.visit_block_mut(&mut node.block);
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, node: &mut syn::TraitItemFn) {
+        if let Some(block) = &mut node.default {
+            self.0.flatten_fn(&node.sig, block);
+            self.visit_block_mut(block);
+        }
+    }
+
+    fn visit_expr_closure_mut(&mut self, _: &mut syn::ExprClosure) {}
+}
+
+impl Rewrites {
+    fn flow_stmt(&self) -> Stmt {
+        syn::parse_quote! { rust_code_obfuscator::obfuscate_flow!(); }
+    }
+
+    fn prepend_flow(&self, block: &mut Block) {
+        if self.flow {
+            block.stmts.insert(0, self.flow_stmt());
+        }
+    }
+}
+
+impl VisitMut for Rewrites {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) if self.strings => {
+                *expr = syn::parse_quote_spanned! {lit.span()=>
+                    rust_code_obfuscator::obfuscate_str!(#lit)
+                };
+            }
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) if self.nums => {
+                *expr = syn::parse_quote_spanned! {lit.span()=>
+                    rust_code_obfuscator::obfuscate_num!(#lit)
+                };
+            }
+            // `-128i8` must be encoded as one literal: `-obfuscate_num!(128i8)`
+            // would overflow before the negation.
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr: operand,
+                ..
+            }) if self.nums
+                && matches!(
+                    &**operand,
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(_),
+                        ..
+                    })
+                ) =>
+            {
+                let Expr::Lit(ExprLit { lit, .. }) = &**operand else {
+                    unreachable!()
+                };
+                *expr = syn::parse_quote_spanned! {lit.span()=>
+                    rust_code_obfuscator::obfuscate_num!(-#lit)
+                };
+            }
+            Expr::If(node) => {
+                self.prepend_flow(&mut node.then_branch);
+                if let Some((_, else_branch)) = &mut node.else_branch {
+                    if let Expr::Block(block) = else_branch.as_mut() {
+                        self.prepend_flow(&mut block.block);
+                    }
+                }
+                syn::visit_mut::visit_expr_mut(self, expr);
+            }
+            Expr::Match(node) => {
+                if self.flow {
+                    for arm in &mut node.arms {
+                        let flow = self.flow_stmt();
+                        let body = &arm.body;
+                        *arm.body = syn::parse_quote!({ #flow #body });
+                    }
+                }
+                syn::visit_mut::visit_expr_mut(self, expr);
+            }
+            Expr::Loop(node) => {
+                self.prepend_flow(&mut node.body);
+                syn::visit_mut::visit_expr_mut(self, expr);
+            }
+            Expr::While(node) => {
+                self.prepend_flow(&mut node.body);
+                syn::visit_mut::visit_expr_mut(self, expr);
+            }
+            Expr::ForLoop(node) => {
+                self.prepend_flow(&mut node.body);
+                syn::visit_mut::visit_expr_mut(self, expr);
+            }
+            Expr::Repeat(node) => self.visit_expr_mut(&mut node.expr),
+            Expr::Const(_) => {}
+            _ => syn::visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_fn_mut(&mut self, node: &mut syn::ItemFn) {
+        if node.sig.constness.is_none() {
+            self.visit_block_mut(&mut node.block);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, node: &mut syn::ImplItemFn) {
+        if node.sig.constness.is_none() {
+            self.visit_block_mut(&mut node.block);
+        }
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, node: &mut syn::TraitItemFn) {
+        if let (None, Some(block)) = (node.sig.constness, &mut node.default) {
+            self.visit_block_mut(block);
+        }
+    }
+
+    fn visit_item_const_mut(&mut self, _: &mut syn::ItemConst) {}
+
+    fn visit_item_static_mut(&mut self, _: &mut syn::ItemStatic) {}
+
+    fn visit_impl_item_const_mut(&mut self, _: &mut syn::ImplItemConst) {}
+
+    fn visit_trait_item_const_mut(&mut self, _: &mut syn::TraitItemConst) {}
+
+    fn visit_variant_mut(&mut self, _: &mut syn::Variant) {}
+
+    fn visit_type_mut(&mut self, _: &mut syn::Type) {}
+
+    fn visit_pat_mut(&mut self, _: &mut syn::Pat) {}
+
+    fn visit_generic_argument_mut(&mut self, _: &mut syn::GenericArgument) {}
+
+    fn visit_attribute_mut(&mut self, _: &mut syn::Attribute) {}
+
+    fn visit_macro_mut(&mut self, _: &mut syn::Macro) {}
+}
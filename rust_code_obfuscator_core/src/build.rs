@@ -1,3 +1,5 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sha2::{Digest, Sha256};
 use std::env;
 
 const KEY_LEN: usize = 32;
@@ -5,6 +7,7 @@ const SALT_LEN: usize = 16;
 
 fn main() {
     let key_hex = env::var("OBFUSCATOR_KEY_HEX").ok();
+    let mut rng = build_rng();
 
     let key_bytes: [u8; KEY_LEN] = if let Some(hex) = key_hex {
         let hex = hex.trim();
@@ -20,13 +23,13 @@ fn main() {
         }
         out
     } else {
-        // Random per-build key
-        rand::random()
+        // Random per-build key (derived from OBFUSCATOR_SEED when set)
+        rng.random()
     };
 
-    let salt: [u8; SALT_LEN] = rand::random();
-    let share_a: [u8; KEY_LEN] = rand::random();
-    let share_b: [u8; KEY_LEN] = rand::random();
+    let salt: [u8; SALT_LEN] = rng.random();
+    let share_a: [u8; KEY_LEN] = rng.random();
+    let share_b: [u8; KEY_LEN] = rng.random();
     let share_c = encode_key_share(&key_bytes, &share_a, &share_b, &salt);
 
     println!("cargo:rustc-env=OBF_KEY_SHARE_A_HEX={}", to_hex(&share_a));
@@ -35,6 +38,29 @@ fn main() {
     println!("cargo:rustc-env=OBF_KEY_SALT_HEX={}", to_hex(&salt));
 
     println!("cargo:rerun-if-env-changed=OBFUSCATOR_KEY_HEX");
+    println!("cargo:rerun-if-env-changed=OBFUSCATOR_SEED");
+}
+
+/// Seeded from `OBFUSCATOR_SEED` for reproducible builds, from the OS otherwise.
+///
+/// The seed derivation mirrors `utils::seeded_bytes(seed, "build-key", &[])`.
+fn build_rng() -> StdRng {
+    match env::var("OBFUSCATOR_SEED").ok().filter(|seed| !seed.is_empty()) {
+        Some(seed) => {
+            let mut hasher = Sha256::new();
+            for part in [
+                b"rustfuscator/seed/v1".as_slice(),
+                seed.as_bytes(),
+                b"build-key",
+                &[],
+            ] {
+                hasher.update((part.len() as u64).to_le_bytes());
+                hasher.update(part);
+            }
+            StdRng::from_seed(hasher.finalize().into())
+        }
+        None => StdRng::from_os_rng(),
+    }
 }
 
 fn encode_key_share(
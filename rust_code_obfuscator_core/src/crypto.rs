@@ -1,15 +1,25 @@
-use aes_gcm::{
-    aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
-    Aes256Gcm, Nonce,
+use aes_gcm::aead::{
+    generic_array::GenericArray, rand_core::RngCore, Aead, AeadInPlace, KeyInit, OsRng, Payload,
+    Tag,
+};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
 };
 use core::str::FromStr;
-use zeroize::Zeroize;
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::errors::ObfuscatorError;
 
 const KEY_LEN: usize = 32;
 const SALT_LEN: usize = 16;
 
+/// HKDF salt for subkey derivation. Changing it invalidates every sealed value.
+const KDF_SALT: &[u8] = b"rustfuscator/kdf/v1";
+
 /// Optional compile-time key material set by build.rs.
 ///
 /// The AES key is not embedded as a single static byte/string sequence. It is
@@ -26,6 +36,30 @@ impl Key {
     pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
         &self.0
     }
+
+    /// Parses a key from 64 hex characters.
+    pub fn from_hex(hex: &str) -> Result<Self, ObfuscatorError> {
+        parse_hex::<KEY_LEN>(hex).map(Key)
+    }
+
+    /// Reassembles a key split into two XOR shares by a literal macro.
+    #[doc(hidden)]
+    pub fn __from_shares(share_a: &[u8; KEY_LEN], share_b: &[u8; KEY_LEN]) -> Self {
+        Key(core::array::from_fn(|i| share_a[i] ^ share_b[i]))
+    }
+}
+
+impl core::fmt::Debug for Key {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Never print key material
+        f.write_str("Key(..)")
+    }
+}
+
+impl From<[u8; KEY_LEN]> for Key {
+    fn from(bytes: [u8; KEY_LEN]) -> Self {
+        Key(bytes)
+    }
 }
 
 impl Drop for Key {
@@ -36,33 +70,102 @@ impl Drop for Key {
 
 fn parse_hex<const N: usize>(s: &str) -> Result<[u8; N], ObfuscatorError> {
     if s.len() != N * 2 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
-        return Err(ObfuscatorError::EncryptionError);
+        return Err(ObfuscatorError::InvalidKeyMaterial);
     }
     let mut out = [0u8; N];
     for i in 0..N {
         out[i] = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
-            .map_err(|_| ObfuscatorError::EncryptionError)?;
+            .map_err(|_| ObfuscatorError::InvalidKeyMaterial)?;
     }
     Ok(out)
 }
 
+/// Whether a missing or malformed build-time share is an error rather than a
+/// fallback to the all-zero key: with the `strict_key` feature, and always in
+/// builds without debug assertions (release profiles).
+pub const STRICT_KEY: bool = cfg!(any(feature = "strict_key", not(debug_assertions)));
+
+#[cfg(any(feature = "strict_key", not(debug_assertions)))]
+const _: () = {
+    assert!(
+        is_hex_share(OBF_KEY_SHARE_A_HEX, KEY_LEN),
+        "strict_key: OBF_KEY_SHARE_A_HEX is missing or malformed; did build.rs run?"
+    );
+    assert!(
+        is_hex_share(OBF_KEY_SHARE_B_HEX, KEY_LEN),
+        "strict_key: OBF_KEY_SHARE_B_HEX is missing or malformed; did build.rs run?"
+    );
+    assert!(
+        is_hex_share(OBF_KEY_SHARE_C_HEX, KEY_LEN),
+        "strict_key: OBF_KEY_SHARE_C_HEX is missing or malformed; did build.rs run?"
+    );
+    assert!(
+        is_hex_share(OBF_KEY_SALT_HEX, SALT_LEN),
+        "strict_key: OBF_KEY_SALT_HEX is missing or malformed; did build.rs run?"
+    );
+};
+
+#[cfg(any(feature = "strict_key", not(debug_assertions)))]
+const fn is_hex_share(share: Option<&str>, len: usize) -> bool {
+    let Some(share) = share else {
+        return false;
+    };
+    let bytes = share.as_bytes();
+    if bytes.len() != len * 2 {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_hexdigit() {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 /// Default key derived from build-time key shares.
 ///
-/// Falls back to all-zeros only in editor/rust-analyzer contexts where build.rs
-/// might not have run.
+/// Outside strict mode (see [`STRICT_KEY`]) this falls back to all-zeros when
+/// build.rs has not run, e.g. in editor/rust-analyzer contexts. In strict mode
+/// the shares are checked at compile time, so this cannot fall back.
 pub fn default_key() -> Key {
-    derive_default_key().unwrap_or_else(|_| Key([0u8; KEY_LEN]))
+    match try_default_key() {
+        Ok(key) => key,
+        Err(err) if STRICT_KEY => panic!("{err}"),
+        Err(_) => Key([0u8; KEY_LEN]),
+    }
+}
+
+/// Default key derived from build-time key shares, or an error naming the
+/// share that is missing or malformed. Never falls back to a fixed key.
+pub fn try_default_key() -> Result<Key, ObfuscatorError> {
+    key_from_shares(
+        OBF_KEY_SHARE_A_HEX,
+        OBF_KEY_SHARE_B_HEX,
+        OBF_KEY_SHARE_C_HEX,
+        OBF_KEY_SALT_HEX,
+    )
+}
+
+fn parse_share<const N: usize>(
+    name: &'static str,
+    share: Option<&str>,
+) -> Result<[u8; N], ObfuscatorError> {
+    let share = share.ok_or(ObfuscatorError::MissingKeyShare { share: name })?;
+    parse_hex::<N>(share).map_err(|_| ObfuscatorError::MalformedKeyShare { share: name })
 }
 
-fn derive_default_key() -> Result<Key, ObfuscatorError> {
-    let mut share_a =
-        parse_hex::<KEY_LEN>(OBF_KEY_SHARE_A_HEX.ok_or(ObfuscatorError::EncryptionError)?)?;
-    let mut share_b =
-        parse_hex::<KEY_LEN>(OBF_KEY_SHARE_B_HEX.ok_or(ObfuscatorError::EncryptionError)?)?;
-    let mut share_c =
-        parse_hex::<KEY_LEN>(OBF_KEY_SHARE_C_HEX.ok_or(ObfuscatorError::EncryptionError)?)?;
-    let mut salt =
-        parse_hex::<SALT_LEN>(OBF_KEY_SALT_HEX.ok_or(ObfuscatorError::EncryptionError)?)?;
+fn key_from_shares(
+    share_a: Option<&str>,
+    share_b: Option<&str>,
+    share_c: Option<&str>,
+    salt: Option<&str>,
+) -> Result<Key, ObfuscatorError> {
+    let mut share_a = parse_share::<KEY_LEN>("OBF_KEY_SHARE_A_HEX", share_a)?;
+    let mut share_b = parse_share::<KEY_LEN>("OBF_KEY_SHARE_B_HEX", share_b)?;
+    let mut share_c = parse_share::<KEY_LEN>("OBF_KEY_SHARE_C_HEX", share_c)?;
+    let mut salt = parse_share::<SALT_LEN>("OBF_KEY_SALT_HEX", salt)?;
 
     let key = core::array::from_fn(|i| {
         share_c[i]
@@ -79,62 +182,1010 @@ fn derive_default_key() -> Result<Key, ObfuscatorError> {
     Ok(Key(key))
 }
 
-fn rotation(index: usize) -> u32 {
-    (index % 7 + 1) as u32
+fn rotation(index: usize) -> u32 {
+    (index % 7 + 1) as u32
+}
+
+fn offset(index: usize) -> u8 {
+    (index as u8).wrapping_mul(17).wrapping_add(91)
+}
+
+/// Describes where a subkey is used.
+///
+/// Every distinct context yields an unrelated subkey, so a key recovered for
+/// one field or call site does not decrypt anything else in the binary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyContext<'a> {
+    pub module_path: &'a str,
+    pub type_name: &'a str,
+    pub field_name: &'a str,
+    pub call_site: u64,
+}
+
+impl<'a> KeyContext<'a> {
+    /// Context for a field of a `#[derive(Obfuscate)]` struct.
+    pub const fn field(module_path: &'a str, type_name: &'a str, field_name: &'a str) -> Self {
+        Self {
+            module_path,
+            type_name,
+            field_name,
+            call_site: 0,
+        }
+    }
+
+    /// Context for a literal macro call site, identified by a per-expansion id.
+    pub const fn call_site(call_site: u64) -> Self {
+        Self {
+            module_path: "",
+            type_name: "",
+            field_name: "",
+            call_site,
+        }
+    }
+
+    /// HKDF `info` input: every string part is length-prefixed so that
+    /// `("ab", "c")` and `("a", "bc")` never collide.
+    fn info(&self) -> Vec<u8> {
+        let mut info = Vec::new();
+        for part in [self.module_path, self.type_name, self.field_name] {
+            info.extend_from_slice(&(part.len() as u32).to_le_bytes());
+            info.extend_from_slice(part.as_bytes());
+        }
+        info.extend_from_slice(&self.call_site.to_le_bytes());
+        info
+    }
+}
+
+/// Derives a subkey of `master` bound to `context` with HKDF-SHA256.
+pub fn derive_subkey(master: &Key, context: &KeyContext<'_>) -> Key {
+    let hkdf = Hkdf::<Sha256>::new(Some(KDF_SALT), master.as_bytes());
+    let mut subkey = [0u8; KEY_LEN];
+    hkdf.expand(&context.info(), &mut subkey)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Key(subkey)
+}
+
+/// An AEAD algorithm that can seal values for the crypto helpers and
+/// `#[derive(Obfuscate)]`.
+///
+/// Implementations are zero-sized marker types; the key is always a 32-byte
+/// [`Key`]. Pick one explicitly with the `*_with::<C>` helpers or with
+/// `#[obfuscator(cipher = ...)]` on a derived struct, or rely on
+/// [`DefaultCipher`].
+pub trait Cipher {
+    /// Algorithm id written into [`Envelope`] headers. Built-in ciphers use
+    /// ids below 128; pick an id of 128 or above for your own implementations.
+    const ID: u8;
+    /// Stable, human-readable algorithm name.
+    const NAME: &'static str;
+    /// Nonce length in bytes. Always equal to the length of [`Cipher::Nonce`].
+    const NONCE_LEN: usize;
+    /// Authentication tag length in bytes appended to every ciphertext.
+    const TAG_LEN: usize;
+
+    /// Fixed-size nonce storage.
+    type Nonce: AsRef<[u8]> + AsMut<[u8]> + Copy + Default + core::fmt::Debug;
+
+    /// Encrypts `plaintext` and authenticates it together with `aad`, which is
+    /// not stored in the output but must be supplied again to decrypt.
+    fn encrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError>;
+
+    fn decrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError>;
+
+    /// Decrypts `buffer` in place, given the authentication `tag` that
+    /// [`Cipher::encrypt`] appended to it.
+    ///
+    /// Used where the clear text must stay in a caller-owned buffer, e.g. a
+    /// stack array. The default goes through [`Cipher::decrypt`] and wipes
+    /// the temporary copy; the built-in ciphers never allocate.
+    fn decrypt_in_place(
+        key: &Key,
+        nonce: &Self::Nonce,
+        buffer: &mut [u8],
+        tag: &[u8],
+        aad: &[u8],
+    ) -> Result<(), ObfuscatorError> {
+        let mut sealed = Vec::with_capacity(buffer.len() + tag.len());
+        sealed.extend_from_slice(buffer);
+        sealed.extend_from_slice(tag);
+        let mut clear = Self::decrypt(key, nonce, &sealed, aad)?;
+        let result = if clear.len() == buffer.len() {
+            buffer.copy_from_slice(&clear);
+            Ok(())
+        } else {
+            Err(ObfuscatorError::DecryptionFailed)
+        };
+        clear.zeroize();
+        result
+    }
+}
+
+/// AES-256-GCM. Fast wherever the CPU has AES instructions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aes256Gcm;
+
+impl Cipher for Aes256Gcm {
+    const ID: u8 = 1;
+    const NAME: &'static str = "AES-256-GCM";
+    const NONCE_LEN: usize = 12;
+    const TAG_LEN: usize = 16;
+
+    type Nonce = [u8; 12];
+
+    fn encrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError> {
+        aead_encrypt::<aes_gcm::Aes256Gcm>(key, nonce, plaintext, aad)
+    }
+
+    fn decrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError> {
+        aead_decrypt::<aes_gcm::Aes256Gcm>(key, nonce, ciphertext, aad)
+    }
+
+    fn decrypt_in_place(
+        key: &Key,
+        nonce: &Self::Nonce,
+        buffer: &mut [u8],
+        tag: &[u8],
+        aad: &[u8],
+    ) -> Result<(), ObfuscatorError> {
+        aead_decrypt_in_place::<aes_gcm::Aes256Gcm>(key, nonce, buffer, tag, aad)
+    }
+}
+
+/// ChaCha20-Poly1305. Constant-time in software, so it is usually the better
+/// choice on targets without AES hardware support.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChaCha20Poly1305;
+
+impl Cipher for ChaCha20Poly1305 {
+    const ID: u8 = 2;
+    const NAME: &'static str = "ChaCha20-Poly1305";
+    const NONCE_LEN: usize = 12;
+    const TAG_LEN: usize = 16;
+
+    type Nonce = [u8; 12];
+
+    fn encrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError> {
+        aead_encrypt::<chacha20poly1305::ChaCha20Poly1305>(key, nonce, plaintext, aad)
+    }
+
+    fn decrypt(
+        key: &Key,
+        nonce: &Self::Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, ObfuscatorError> {
+        aead_decrypt::<chacha20poly1305::ChaCha20Poly1305>(key, nonce, ciphertext, aad)
+    }
+
+    fn decrypt_in_place(
+        key: &Key,
+        nonce: &Self::Nonce,
+        buffer: &mut [u8],
+        tag: &[u8],
+        aad: &[u8],
+    ) -> Result<(), ObfuscatorError> {
+        aead_decrypt_in_place::<chacha20poly1305::ChaCha20Poly1305>(key, nonce, buffer, tag, aad)
+    }
+}
+
+/// Cipher used by the non-generic helpers and by `#[derive(Obfuscate)]`.
+///
+/// AES-256-GCM unless the `chacha20poly1305_default` feature is enabled.
+#[cfg(not(feature = "chacha20poly1305_default"))]
+pub type DefaultCipher = Aes256Gcm;
+
+/// Cipher used by the non-generic helpers and by `#[derive(Obfuscate)]`.
+///
+/// AES-256-GCM unless the `chacha20poly1305_default` feature is enabled.
+#[cfg(feature = "chacha20poly1305_default")]
+pub type DefaultCipher = ChaCha20Poly1305;
+
+/// Nonce type of [`DefaultCipher`].
+pub type DefaultNonce = <DefaultCipher as Cipher>::Nonce;
+
+fn aead_encrypt<A: Aead + KeyInit>(
+    key: &Key,
+    nonce: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, ObfuscatorError> {
+    let cipher = A::new_from_slice(key.as_bytes()).map_err(|_| ObfuscatorError::EncryptionError)?;
+    cipher
+        .encrypt(
+            GenericArray::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| ObfuscatorError::EncryptionError)
+}
+
+fn aead_decrypt<A: Aead + KeyInit>(
+    key: &Key,
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, ObfuscatorError> {
+    let cipher =
+        A::new_from_slice(key.as_bytes()).map_err(|_| ObfuscatorError::DecryptionFailed)?;
+    cipher
+        .decrypt(
+            GenericArray::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| ObfuscatorError::TamperDetected)
+}
+
+fn aead_decrypt_in_place<A: AeadInPlace + KeyInit>(
+    key: &Key,
+    nonce: &[u8],
+    buffer: &mut [u8],
+    tag: &[u8],
+    aad: &[u8],
+) -> Result<(), ObfuscatorError> {
+    let cipher =
+        A::new_from_slice(key.as_bytes()).map_err(|_| ObfuscatorError::DecryptionFailed)?;
+    if tag.len() != Tag::<A>::default().len() {
+        return Err(ObfuscatorError::TamperDetected);
+    }
+    cipher
+        .decrypt_in_place_detached(
+            GenericArray::from_slice(nonce),
+            aad,
+            buffer,
+            Tag::<A>::from_slice(tag),
+        )
+        .map_err(|_| ObfuscatorError::TamperDetected)
+}
+
+fn random_nonce<C: Cipher>() -> C::Nonce {
+    let mut nonce = C::Nonce::default();
+    OsRng.fill_bytes(nonce.as_mut());
+    nonce
+}
+
+pub fn encrypt_string(input: &str, key: &Key) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
+    encrypt_string_with::<DefaultCipher>(input, key)
+}
+
+pub fn decrypt_string(
+    data: &[u8],
+    nonce: &DefaultNonce,
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    decrypt_string_with::<DefaultCipher>(data, nonce, key)
+}
+
+pub fn encrypt_string_with<C: Cipher>(
+    input: &str,
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    encrypt_string_with_aad::<C>(input, &[], key)
+}
+
+pub fn decrypt_string_with<C: Cipher>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    decrypt_string_with_aad::<C>(data, nonce, &[], key)
+}
+
+/// Like [`encrypt_string_with`], but binds the ciphertext to `aad`: it only
+/// decrypts when the same associated data is passed to
+/// [`decrypt_string_with_aad`].
+pub fn encrypt_string_with_aad<C: Cipher>(
+    input: &str,
+    aad: &[u8],
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    encrypt_bytes_with_aad::<C>(input.as_bytes(), aad, key)
+}
+
+pub fn decrypt_string_with_aad<C: Cipher>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    aad: &[u8],
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    utf8_plaintext(decrypt_bytes_with_aad::<C>(data, nonce, aad, key)?)
+}
+
+/// Encrypts arbitrary binary data (certificates, public keys, tables, ...).
+pub fn encrypt_bytes(input: &[u8], key: &Key) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
+    encrypt_bytes_with::<DefaultCipher>(input, key)
+}
+
+pub fn decrypt_bytes(
+    data: &[u8],
+    nonce: &DefaultNonce,
+    key: &Key,
+) -> Result<Vec<u8>, ObfuscatorError> {
+    decrypt_bytes_with::<DefaultCipher>(data, nonce, key)
+}
+
+pub fn encrypt_bytes_with<C: Cipher>(
+    input: &[u8],
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    encrypt_bytes_with_aad::<C>(input, &[], key)
+}
+
+pub fn decrypt_bytes_with<C: Cipher>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    key: &Key,
+) -> Result<Vec<u8>, ObfuscatorError> {
+    decrypt_bytes_with_aad::<C>(data, nonce, &[], key)
+}
+
+pub fn encrypt_bytes_with_aad<C: Cipher>(
+    input: &[u8],
+    aad: &[u8],
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    let nonce = random_nonce::<C>();
+    let ciphertext = C::encrypt(key, &nonce, input, aad)?;
+    Ok((ciphertext, nonce))
+}
+
+pub fn decrypt_bytes_with_aad<C: Cipher>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    aad: &[u8],
+    key: &Key,
+) -> Result<Vec<u8>, ObfuscatorError> {
+    C::decrypt(key, nonce, data, aad)
+}
+
+/// Associated data `#[derive(Obfuscate)]` binds each field's ciphertext to,
+/// so it cannot be moved into another field or another struct type.
+pub fn field_aad(type_name: &str, field_name: &str) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(8 + type_name.len() + field_name.len());
+    for part in [type_name, field_name] {
+        aad.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        aad.extend_from_slice(part.as_bytes());
+    }
+    aad
+}
+
+/// Length-hiding padding applied to a plaintext before it is encrypted.
+///
+/// The padded payload is `len: u32 LE | plaintext | zero bytes`, so the real
+/// length is authenticated along with the value and the ciphertext only
+/// reveals which bucket the plaintext falls into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    /// Round the payload up to the next power of two.
+    PowerOfTwo,
+    /// Round the payload up to a multiple of the given size (0 acts as 1).
+    Bucket(usize),
+}
+
+/// Size of the length prefix at the start of a padded payload.
+pub const PADDING_HEADER_LEN: usize = 4;
+
+impl Padding {
+    /// Length of the padded payload for a plaintext of `len` bytes.
+    pub fn padded_len(self, len: usize) -> usize {
+        let framed = PADDING_HEADER_LEN + len;
+        match self {
+            Padding::PowerOfTwo => framed.next_power_of_two(),
+            Padding::Bucket(size) => framed.div_ceil(size.max(1)) * size.max(1),
+        }
+    }
+}
+
+/// Frames `plaintext` with its length and pads it according to `padding`.
+pub fn pad(plaintext: &[u8], padding: Padding) -> Result<Vec<u8>, ObfuscatorError> {
+    let len = u32::try_from(plaintext.len()).map_err(|_| ObfuscatorError::EncryptionError)?;
+    let mut padded = Vec::with_capacity(padding.padded_len(plaintext.len()));
+    padded.extend_from_slice(&len.to_le_bytes());
+    padded.extend_from_slice(plaintext);
+    padded.resize(padding.padded_len(plaintext.len()), 0);
+    Ok(padded)
+}
+
+/// Returns the plaintext framed by [`pad`].
+pub fn unpad(padded: &[u8]) -> Result<&[u8], ObfuscatorError> {
+    let (header, rest) = padded
+        .split_first_chunk::<PADDING_HEADER_LEN>()
+        .ok_or(ObfuscatorError::InvalidPadding)?;
+    let len = u32::from_le_bytes(*header) as usize;
+    rest.get(..len).ok_or(ObfuscatorError::InvalidPadding)
+}
+
+pub fn encrypt_string_padded(
+    input: &str,
+    padding: Padding,
+    key: &Key,
+) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
+    encrypt_string_padded_with::<DefaultCipher>(input, padding, key)
+}
+
+pub fn decrypt_string_padded(
+    data: &[u8],
+    nonce: &DefaultNonce,
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    decrypt_string_padded_with::<DefaultCipher>(data, nonce, key)
+}
+
+/// Like [`encrypt_string_with`], but pads the plaintext first so the
+/// ciphertext length only reveals its [`Padding`] bucket.
+pub fn encrypt_string_padded_with<C: Cipher>(
+    input: &str,
+    padding: Padding,
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    let padded = pad(input.as_bytes(), padding)?;
+    let encrypted = encrypt_bytes_with::<C>(&padded, key);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut padded = padded;
+        padded.zeroize();
+    }
+    encrypted
+}
+
+pub fn decrypt_string_padded_with<C: Cipher>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    unpadded_utf8(decrypt_bytes_with::<C>(data, nonce, key)?)
+}
+
+/// Strips the padding off a decrypted buffer. The buffer holds the plaintext
+/// too, so it is zeroized whether or not `secure_zeroize` is enabled.
+fn unpadded_utf8(padded: Vec<u8>) -> Result<String, ObfuscatorError> {
+    let padded = Zeroizing::new(padded);
+    utf8_plaintext(unpad(&padded)?.to_vec())
+}
+
+fn utf8_plaintext(plaintext: Vec<u8>) -> Result<String, ObfuscatorError> {
+    match String::from_utf8(plaintext) {
+        Ok(value) => Ok(value),
+        Err(err) => {
+            let source = err.utf8_error();
+            #[cfg(feature = "secure_zeroize")]
+            {
+                let mut bytes = err.into_bytes();
+                bytes.zeroize();
+            }
+            Err(ObfuscatorError::InvalidUtf8 { source })
+        }
+    }
+}
+
+/// Current [`Envelope`] format version.
+///
+/// - 2: scalars are sealed as fixed-width [`ScalarCodec`] bytes.
+/// - 1: scalars were sealed as `Display` text; [`open_scalar`] still reads them.
+pub const ENVELOPE_VERSION: u8 = 2;
+
+/// First [`Envelope`] version whose scalars are [`ScalarCodec`] encoded.
+pub const BINARY_SCALAR_VERSION: u8 = 2;
+
+/// Format version assigned to values converted with [`Envelope::from_legacy`].
+pub const LEGACY_ENVELOPE_VERSION: u8 = 0;
+
+// version + algorithm + key id + nonce length
+const ENVELOPE_HEADER_LEN: usize = 1 + 1 + 4 + 1;
+
+/// Self-describing ciphertext: records which format, algorithm and key
+/// produced it, so values stay readable after the default cipher changes or
+/// the key is rotated.
+///
+/// Wire format (see [`Envelope::encode`]):
+///
+/// ```text
+/// version: u8 | algorithm: u8 | key_id: u32 LE | nonce_len: u8 | nonce | ciphertext
+/// ```
+///
+/// The header is authenticated along with the caller's associated data, so
+/// rewriting the key id or algorithm makes the envelope fail to open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    version: u8,
+    algorithm: u8,
+    key_id: u32,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Seals `plaintext` with cipher `C` under `key`, recording `key_id` and
+    /// binding it to `aad`.
+    pub fn seal<C: Cipher>(
+        plaintext: &[u8],
+        aad: &[u8],
+        key_id: u32,
+        key: &Key,
+    ) -> Result<Self, ObfuscatorError> {
+        let nonce = random_nonce::<C>();
+        let mut envelope = Self {
+            version: ENVELOPE_VERSION,
+            algorithm: C::ID,
+            key_id,
+            nonce: nonce.as_ref().to_vec(),
+            ciphertext: Vec::new(),
+        };
+        envelope.ciphertext = C::encrypt(key, &nonce, plaintext, &envelope.full_aad(aad))?;
+        Ok(envelope)
+    }
+
+    /// Wraps a `(ciphertext, nonce)` pair produced by rustfuscator 0.3 and
+    /// earlier, which was always AES-256-GCM under the build key itself.
+    ///
+    /// Open it with [`Envelope::open`], the master key and empty associated
+    /// data, then re-seal it.
+    pub fn from_legacy(ciphertext: Vec<u8>, nonce: [u8; 12]) -> Self {
+        Self {
+            version: LEGACY_ENVELOPE_VERSION,
+            algorithm: Aes256Gcm::ID,
+            key_id: 0,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    pub fn nonce(&self) -> &[u8] {
+        &self.nonce
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    /// Decrypts with whichever built-in cipher the header names. `aad` must
+    /// match what the envelope was sealed with.
+    pub fn open(&self, key: &Key, aad: &[u8]) -> Result<Vec<u8>, ObfuscatorError> {
+        match self.algorithm {
+            Aes256Gcm::ID => self.open_as::<Aes256Gcm>(key, aad),
+            ChaCha20Poly1305::ID => self.open_as::<ChaCha20Poly1305>(key, aad),
+            id => Err(ObfuscatorError::InvalidEnvelope {
+                reason: format!("unknown algorithm id {id}"),
+            }),
+        }
+    }
+
+    /// Decrypts with `C`, which the header must name. Use this for envelopes
+    /// sealed by a custom [`Cipher`], or to pin the cipher; [`Envelope::open`]
+    /// follows the header instead.
+    pub fn open_with<C: Cipher>(&self, key: &Key, aad: &[u8]) -> Result<Vec<u8>, ObfuscatorError> {
+        if self.algorithm != C::ID {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: format!(
+                    "algorithm mismatch: header names id {}, expected {} (id {})",
+                    self.algorithm,
+                    C::NAME,
+                    C::ID
+                ),
+            });
+        }
+        self.open_as::<C>(key, aad)
+    }
+
+    fn open_as<C: Cipher>(&self, key: &Key, aad: &[u8]) -> Result<Vec<u8>, ObfuscatorError> {
+        if self.version > ENVELOPE_VERSION {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: format!("unsupported format version {}", self.version),
+            });
+        }
+        if self.nonce.len() != C::NONCE_LEN {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: format!("{} expects a {}-byte nonce", C::NAME, C::NONCE_LEN),
+            });
+        }
+        let mut nonce = C::Nonce::default();
+        nonce.as_mut().copy_from_slice(&self.nonce);
+        C::decrypt(key, &nonce, &self.ciphertext, &self.full_aad(aad))
+    }
+
+    /// Header followed by the caller's associated data. Legacy values were
+    /// sealed without either.
+    fn full_aad(&self, aad: &[u8]) -> Vec<u8> {
+        if self.version == LEGACY_ENVELOPE_VERSION {
+            return aad.to_vec();
+        }
+        let mut full = Vec::with_capacity(ENVELOPE_HEADER_LEN + aad.len());
+        self.write_header(&mut full);
+        full.extend_from_slice(aad);
+        full
+    }
+
+    fn write_header(&self, out: &mut Vec<u8>) {
+        out.push(self.version);
+        out.push(self.algorithm);
+        out.extend_from_slice(&self.key_id.to_le_bytes());
+        out.push(self.nonce.len() as u8);
+    }
+
+    /// Serializes the envelope into its wire format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(ENVELOPE_HEADER_LEN + self.nonce.len() + self.ciphertext.len());
+        self.write_header(&mut out);
+        out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Parses an envelope previously produced by [`Envelope::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+        if bytes.len() < ENVELOPE_HEADER_LEN {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: "truncated header".to_string(),
+            });
+        }
+        let version = bytes[0];
+        if version > ENVELOPE_VERSION {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: format!("unsupported format version {version}"),
+            });
+        }
+        let algorithm = bytes[1];
+        let key_id = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]);
+        let nonce_len = bytes[6] as usize;
+        let body = &bytes[ENVELOPE_HEADER_LEN..];
+        if body.len() < nonce_len {
+            return Err(ObfuscatorError::InvalidEnvelope {
+                reason: "truncated nonce".to_string(),
+            });
+        }
+        let (nonce, ciphertext) = body.split_at(nonce_len);
+        Ok(Self {
+            version,
+            algorithm,
+            key_id,
+            nonce: nonce.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+impl Zeroize for Envelope {
+    fn zeroize(&mut self) {
+        self.nonce.zeroize();
+        self.ciphertext.zeroize();
+    }
+}
+
+/// Seals a string into an [`Envelope`].
+pub fn seal_string<C: Cipher>(
+    input: &str,
+    aad: &[u8],
+    key_id: u32,
+    key: &Key,
+) -> Result<Envelope, ObfuscatorError> {
+    Envelope::seal::<C>(input.as_bytes(), aad, key_id, key)
+}
+
+/// Pads a string with [`pad`] and seals it into an [`Envelope`].
+pub fn seal_string_padded<C: Cipher>(
+    input: &str,
+    padding: Padding,
+    aad: &[u8],
+    key_id: u32,
+    key: &Key,
+) -> Result<Envelope, ObfuscatorError> {
+    let padded = pad(input.as_bytes(), padding)?;
+    let sealed = Envelope::seal::<C>(&padded, aad, key_id, key);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut padded = padded;
+        padded.zeroize();
+    }
+    sealed
+}
+
+/// Seals the `Display` form of a scalar into an [`Envelope`].
+pub fn seal_display<C: Cipher, T: core::fmt::Display>(
+    input: T,
+    aad: &[u8],
+    key_id: u32,
+    key: &Key,
+) -> Result<Envelope, ObfuscatorError> {
+    let clear = input.to_string();
+    let sealed = seal_string::<C>(&clear, aad, key_id, key);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut clear = clear;
+        clear.zeroize();
+    }
+    sealed
+}
+
+/// Opens an [`Envelope`] holding a string, see [`Envelope::open_with`].
+pub fn open_string<C: Cipher>(
+    envelope: &Envelope,
+    aad: &[u8],
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    utf8_plaintext(envelope.open_with::<C>(key, aad)?)
+}
+
+/// Opens an [`Envelope`] sealed by [`seal_string_padded`] and strips the padding.
+pub fn open_string_padded<C: Cipher>(
+    envelope: &Envelope,
+    aad: &[u8],
+    key: &Key,
+) -> Result<String, ObfuscatorError> {
+    unpadded_utf8(envelope.open_with::<C>(key, aad)?)
+}
+
+/// Opens an [`Envelope`] sealed by [`seal_display`] and parses it back.
+pub fn open_parse<C: Cipher, T: FromStr>(
+    envelope: &Envelope,
+    aad: &[u8],
+    key: &Key,
+) -> Result<T, ObfuscatorError> {
+    let s = open_string::<C>(envelope, aad, key)?;
+    let parsed = s.parse().map_err(|_| ObfuscatorError::ParseFailed {
+        type_name: core::any::type_name::<T>(),
+    });
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut s = s;
+        s.zeroize();
+    }
+    parsed
+}
+
+/// Fixed-width little-endian encoding of a scalar.
+///
+/// Every value of a type encodes to the same number of bytes, so the
+/// ciphertext length does not reveal its magnitude, and floats round-trip
+/// bit for bit. `usize`/`isize` are encoded as 64-bit values so sealed data
+/// moves between targets.
+pub trait ScalarCodec: Sized {
+    /// Encoded form, `[u8; N]`.
+    type Bytes: AsRef<[u8]> + Zeroize;
+
+    fn to_le_bytes(&self) -> Self::Bytes;
+
+    /// Fails with [`ObfuscatorError::ParseFailed`] on a length mismatch or an
+    /// invalid value (e.g. a `bool` byte other than 0 or 1).
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError>;
+}
+
+fn parse_failed<T>() -> ObfuscatorError {
+    ObfuscatorError::ParseFailed {
+        type_name: core::any::type_name::<T>(),
+    }
+}
+
+fn fixed<T, const N: usize>(bytes: &[u8]) -> Result<[u8; N], ObfuscatorError> {
+    bytes.try_into().map_err(|_| parse_failed::<T>())
+}
+
+macro_rules! impl_scalar_codec_le {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ScalarCodec for $ty {
+                type Bytes = [u8; core::mem::size_of::<$ty>()];
+
+                fn to_le_bytes(&self) -> Self::Bytes {
+                    <$ty>::to_le_bytes(*self)
+                }
+
+                fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+                    fixed::<$ty, { core::mem::size_of::<$ty>() }>(bytes).map(<$ty>::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_codec_le!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl ScalarCodec for usize {
+    type Bytes = [u8; 8];
+
+    fn to_le_bytes(&self) -> Self::Bytes {
+        (*self as u64).to_le_bytes()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+        let value = u64::from_le_bytes(fixed::<usize, 8>(bytes)?);
+        usize::try_from(value).map_err(|_| parse_failed::<usize>())
+    }
+}
+
+impl ScalarCodec for isize {
+    type Bytes = [u8; 8];
+
+    fn to_le_bytes(&self) -> Self::Bytes {
+        (*self as i64).to_le_bytes()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+        let value = i64::from_le_bytes(fixed::<isize, 8>(bytes)?);
+        isize::try_from(value).map_err(|_| parse_failed::<isize>())
+    }
+}
+
+impl ScalarCodec for bool {
+    type Bytes = [u8; 1];
+
+    fn to_le_bytes(&self) -> Self::Bytes {
+        [*self as u8]
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+        match fixed::<bool, 1>(bytes)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(parse_failed::<bool>()),
+        }
+    }
+}
+
+impl ScalarCodec for char {
+    type Bytes = [u8; 4];
+
+    fn to_le_bytes(&self) -> Self::Bytes {
+        u32::from(*self).to_le_bytes()
+    }
+
+    fn from_le_bytes(bytes: &[u8]) -> Result<Self, ObfuscatorError> {
+        let value = u32::from_le_bytes(fixed::<char, 4>(bytes)?);
+        char::from_u32(value).ok_or_else(parse_failed::<char>)
+    }
+}
+
+pub fn encrypt_scalar<T: ScalarCodec>(
+    input: T,
+    key: &Key,
+) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
+    encrypt_scalar_with::<DefaultCipher, T>(input, key)
+}
+
+pub fn decrypt_scalar<T: ScalarCodec>(
+    data: &[u8],
+    nonce: &DefaultNonce,
+    key: &Key,
+) -> Result<T, ObfuscatorError> {
+    decrypt_scalar_with::<DefaultCipher, T>(data, nonce, key)
+}
+
+pub fn encrypt_scalar_with<C: Cipher, T: ScalarCodec>(
+    input: T,
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
+    let clear = input.to_le_bytes();
+    let encrypted = encrypt_bytes_with::<C>(clear.as_ref(), key);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut clear = clear;
+        clear.zeroize();
+    }
+    encrypted
 }
 
-fn offset(index: usize) -> u8 {
-    (index as u8).wrapping_mul(17).wrapping_add(91)
+pub fn decrypt_scalar_with<C: Cipher, T: ScalarCodec>(
+    data: &[u8],
+    nonce: &C::Nonce,
+    key: &Key,
+) -> Result<T, ObfuscatorError> {
+    let clear = decrypt_bytes_with::<C>(data, nonce, key)?;
+    let value = T::from_le_bytes(&clear);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut clear = clear;
+        clear.zeroize();
+    }
+    value
 }
 
-pub fn encrypt_string(input: &str, key: &Key) -> Result<(Vec<u8>, [u8; 12]), ObfuscatorError> {
-    let cipher =
-        Aes256Gcm::new_from_slice(key.as_bytes()).map_err(|_| ObfuscatorError::EncryptionError)?;
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let ciphertext = cipher
-        .encrypt(nonce, input.as_bytes())
-        .map_err(|_| ObfuscatorError::EncryptionError)?;
-    Ok((ciphertext, nonce_bytes))
+/// Seals a scalar into an [`Envelope`] using its [`ScalarCodec`] encoding.
+pub fn seal_scalar<C: Cipher, T: ScalarCodec>(
+    input: T,
+    aad: &[u8],
+    key_id: u32,
+    key: &Key,
+) -> Result<Envelope, ObfuscatorError> {
+    let clear = input.to_le_bytes();
+    let sealed = Envelope::seal::<C>(clear.as_ref(), aad, key_id, key);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut clear = clear;
+        clear.zeroize();
+    }
+    sealed
 }
 
-pub fn decrypt_string(data: &[u8], nonce: &[u8; 12], key: &Key) -> Result<String, ObfuscatorError> {
-    let cipher =
-        Aes256Gcm::new_from_slice(key.as_bytes()).map_err(|_| ObfuscatorError::EncryptionError)?;
-    let nonce = Nonce::from_slice(nonce);
-    let plaintext = cipher
-        .decrypt(nonce, data)
-        .map_err(|_| ObfuscatorError::EncryptionError)?;
-    match String::from_utf8(plaintext) {
-        Ok(value) => Ok(value),
-        Err(err) => {
-            #[cfg(feature = "secure_zeroize")]
-            {
-                let mut bytes = err.into_bytes();
-                bytes.zeroize();
-            }
-            #[cfg(not(feature = "secure_zeroize"))]
-            let _ = err;
-            Err(ObfuscatorError::EncryptionError)
-        }
+/// Opens a scalar sealed by [`seal_scalar`]. Envelopes older than
+/// [`BINARY_SCALAR_VERSION`] hold `Display` text and are parsed instead.
+pub fn open_scalar<C: Cipher, T: ScalarCodec + FromStr>(
+    envelope: &Envelope,
+    aad: &[u8],
+    key: &Key,
+) -> Result<T, ObfuscatorError> {
+    if envelope.version() < BINARY_SCALAR_VERSION {
+        return open_parse::<C, T>(envelope, aad, key);
+    }
+    let clear = envelope.open_with::<C>(key, aad)?;
+    let value = T::from_le_bytes(&clear);
+    #[cfg(feature = "secure_zeroize")]
+    {
+        let mut clear = clear;
+        clear.zeroize();
     }
+    value
 }
 
-pub fn encrypt_u32(input: u32, key: &Key) -> Result<(Vec<u8>, [u8; 12]), ObfuscatorError> {
+pub fn encrypt_u32(input: u32, key: &Key) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
     encrypt_display(input, key)
 }
 
-pub fn decrypt_u32(data: &[u8], nonce: &[u8; 12], key: &Key) -> Result<u32, ObfuscatorError> {
+pub fn decrypt_u32(data: &[u8], nonce: &DefaultNonce, key: &Key) -> Result<u32, ObfuscatorError> {
     decrypt_parse(data, nonce, key)
 }
 
 pub fn encrypt_display<T: core::fmt::Display>(
     input: T,
     key: &Key,
-) -> Result<(Vec<u8>, [u8; 12]), ObfuscatorError> {
+) -> Result<(Vec<u8>, DefaultNonce), ObfuscatorError> {
+    encrypt_display_with::<DefaultCipher, T>(input, key)
+}
+
+pub fn decrypt_parse<T: FromStr>(
+    data: &[u8],
+    nonce: &DefaultNonce,
+    key: &Key,
+) -> Result<T, ObfuscatorError> {
+    decrypt_parse_with::<DefaultCipher, T>(data, nonce, key)
+}
+
+pub fn encrypt_display_with<C: Cipher, T: core::fmt::Display>(
+    input: T,
+    key: &Key,
+) -> Result<(Vec<u8>, C::Nonce), ObfuscatorError> {
     let clear = input.to_string();
-    let encrypted = encrypt_string(&clear, key);
+    let encrypted = encrypt_string_with::<C>(&clear, key);
     #[cfg(feature = "secure_zeroize")]
     {
         let mut clear = clear;
@@ -143,13 +1194,15 @@ pub fn encrypt_display<T: core::fmt::Display>(
     encrypted
 }
 
-pub fn decrypt_parse<T: FromStr>(
+pub fn decrypt_parse_with<C: Cipher, T: FromStr>(
     data: &[u8],
-    nonce: &[u8; 12],
+    nonce: &C::Nonce,
     key: &Key,
 ) -> Result<T, ObfuscatorError> {
-    let s = decrypt_string(data, nonce, key)?;
-    let parsed = s.parse().map_err(|_| ObfuscatorError::EncryptionError);
+    let s = decrypt_string_with::<C>(data, nonce, key)?;
+    let parsed = s.parse().map_err(|_| ObfuscatorError::ParseFailed {
+        type_name: core::any::type_name::<T>(),
+    });
     #[cfg(feature = "secure_zeroize")]
     {
         let mut s = s;
@@ -231,7 +1284,10 @@ mod tests {
         let k = create_new_key();
         let (ct, nonce) = encrypt_string("not-a-bool", &k).unwrap();
 
-        assert!(decrypt_parse::<bool>(&ct, &nonce, &k).is_err());
+        assert!(matches!(
+            decrypt_parse::<bool>(&ct, &nonce, &k),
+            Err(ObfuscatorError::ParseFailed { type_name: "bool" })
+        ));
     }
 
     #[test]
@@ -258,11 +1314,483 @@ mod tests {
         assert!(option_env!("OBF_KEY_HEX").is_none());
     }
 
+    #[test]
+    fn encrypt_and_decrypt_with_every_builtin_cipher() {
+        let k = create_new_key();
+
+        let (ct, nonce) = encrypt_string_with::<Aes256Gcm>("aes data", &k).unwrap();
+        assert_eq!(ct.len(), "aes data".len() + Aes256Gcm::TAG_LEN);
+        assert_eq!(nonce.len(), Aes256Gcm::NONCE_LEN);
+        assert_eq!(
+            decrypt_string_with::<Aes256Gcm>(&ct, &nonce, &k).unwrap(),
+            "aes data"
+        );
+
+        let (ct, nonce) = encrypt_string_with::<ChaCha20Poly1305>("chacha data", &k).unwrap();
+        assert_eq!(ct.len(), "chacha data".len() + ChaCha20Poly1305::TAG_LEN);
+        assert_eq!(nonce.len(), ChaCha20Poly1305::NONCE_LEN);
+        assert_eq!(
+            decrypt_string_with::<ChaCha20Poly1305>(&ct, &nonce, &k).unwrap(),
+            "chacha data"
+        );
+
+        let (ct, nonce) = encrypt_display_with::<ChaCha20Poly1305, _>(-7i64, &k).unwrap();
+        assert_eq!(
+            decrypt_parse_with::<ChaCha20Poly1305, i64>(&ct, &nonce, &k).unwrap(),
+            -7
+        );
+    }
+
+    #[test]
+    fn ciphertext_does_not_open_under_a_different_cipher() {
+        let k = create_new_key();
+        let (ct, nonce) = encrypt_string_with::<Aes256Gcm>("secret", &k).unwrap();
+        assert!(decrypt_string_with::<ChaCha20Poly1305>(&ct, &nonce, &k).is_err());
+    }
+
+    #[test]
+    fn derived_subkeys_are_deterministic_and_context_bound() {
+        let master = create_new_key();
+        let token = KeyContext::field("app::session", "ApiSession", "token");
+        let username = KeyContext::field("app::session", "ApiSession", "username");
+
+        let a = derive_subkey(&master, &token);
+        let b = derive_subkey(&master, &token);
+        assert_eq!(a.as_bytes(), b.as_bytes());
+        assert_ne!(a.as_bytes(), master.as_bytes());
+        assert_ne!(a.as_bytes(), derive_subkey(&master, &username).as_bytes());
+        assert_ne!(
+            derive_subkey(&master, &KeyContext::call_site(1)).as_bytes(),
+            derive_subkey(&master, &KeyContext::call_site(2)).as_bytes()
+        );
+    }
+
+    #[test]
+    fn key_context_parts_are_length_prefixed() {
+        let master = create_new_key();
+        let left = KeyContext::field("m", "ab", "c");
+        let right = KeyContext::field("m", "a", "bc");
+
+        assert_ne!(
+            derive_subkey(&master, &left).as_bytes(),
+            derive_subkey(&master, &right).as_bytes()
+        );
+    }
+
+    #[test]
+    fn value_sealed_under_subkey_does_not_open_with_master() {
+        let master = create_new_key();
+        let subkey = derive_subkey(&master, &KeyContext::field("m", "T", "f"));
+        let (ct, nonce) = encrypt_string("secret", &subkey).unwrap();
+
+        assert!(decrypt_string(&ct, &nonce, &master).is_err());
+        assert_eq!(decrypt_string(&ct, &nonce, &subkey).unwrap(), "secret");
+    }
+
+    #[test]
+    fn envelope_round_trips_through_wire_format() {
+        let k = create_new_key();
+        let sealed = Envelope::seal::<ChaCha20Poly1305>(b"rotate me", b"ctx", 7, &k).unwrap();
+
+        let decoded = Envelope::decode(&sealed.encode()).unwrap();
+
+        assert_eq!(decoded, sealed);
+        assert_eq!(decoded.version(), ENVELOPE_VERSION);
+        assert_eq!(decoded.algorithm(), ChaCha20Poly1305::ID);
+        assert_eq!(decoded.key_id(), 7);
+        assert_eq!(decoded.nonce().len(), ChaCha20Poly1305::NONCE_LEN);
+        assert_eq!(decoded.open(&k, b"ctx").unwrap(), b"rotate me");
+    }
+
+    #[test]
+    fn envelope_opens_with_algorithm_named_in_header() {
+        let k = create_new_key();
+        let sealed = seal_string::<Aes256Gcm>("aes", &[], 0, &k).unwrap();
+
+        assert_eq!(sealed.open(&k, &[]).unwrap(), b"aes");
+    }
+
+    #[test]
+    fn envelope_open_with_rejects_other_algorithm() {
+        let k = create_new_key();
+        let sealed = seal_string::<Aes256Gcm>("aes", &[], 0, &k).unwrap();
+
+        match open_string::<ChaCha20Poly1305>(&sealed, &[], &k) {
+            Err(ObfuscatorError::InvalidEnvelope { reason }) => {
+                assert!(reason.starts_with("algorithm mismatch"), "{reason}")
+            }
+            other => panic!("expected an algorithm mismatch, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn envelope_decode_rejects_malformed_input() {
+        assert!(Envelope::decode(&[1, 1, 0]).is_err());
+        assert!(Envelope::decode(&[1, 1, 0, 0, 0, 0, 12, 0xaa]).is_err());
+        assert!(Envelope::decode(&[ENVELOPE_VERSION + 1, 1, 0, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn envelope_rejects_unknown_algorithm() {
+        let k = create_new_key();
+        let mut bytes = seal_string::<Aes256Gcm>("x", &[], 0, &k).unwrap().encode();
+        bytes[1] = 200;
+
+        assert!(Envelope::decode(&bytes).unwrap().open(&k, &[]).is_err());
+    }
+
+    #[test]
+    fn legacy_pairs_open_as_aes_envelopes() {
+        let k = create_new_key();
+        let (ct, nonce) = encrypt_string_with::<Aes256Gcm>("old data", &k).unwrap();
+
+        let legacy = Envelope::from_legacy(ct, nonce);
+
+        assert_eq!(legacy.version(), LEGACY_ENVELOPE_VERSION);
+        assert_eq!(
+            open_string::<Aes256Gcm>(&legacy, &[], &k).unwrap(),
+            "old data"
+        );
+        assert_eq!(
+            Envelope::decode(&legacy.encode())
+                .unwrap()
+                .open(&k, &[])
+                .unwrap(),
+            b"old data"
+        );
+    }
+
+    #[test]
+    fn string_bound_to_aad_only_opens_with_same_aad() {
+        let k = create_new_key();
+        let aad = field_aad("ApiSession", "token");
+        let (ct, nonce) = encrypt_string_with_aad::<Aes256Gcm>("t0k3n", &aad, &k).unwrap();
+
+        assert_eq!(
+            decrypt_string_with_aad::<Aes256Gcm>(&ct, &nonce, &aad, &k).unwrap(),
+            "t0k3n"
+        );
+        assert!(decrypt_string_with::<Aes256Gcm>(&ct, &nonce, &k).is_err());
+        assert!(decrypt_string_with_aad::<Aes256Gcm>(
+            &ct,
+            &nonce,
+            &field_aad("ApiSession", "username"),
+            &k
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_bytes() {
+        let k = create_new_key();
+        let blob: Vec<u8> = (0..=255u8).chain([0, 0xff, 0]).collect();
+
+        let (ct, nonce) = encrypt_bytes(&blob, &k).unwrap();
+        assert_eq!(decrypt_bytes(&ct, &nonce, &k).unwrap(), blob);
+
+        let (ct, nonce) = encrypt_bytes_with::<ChaCha20Poly1305>(&[], &k).unwrap();
+        assert!(decrypt_bytes_with::<ChaCha20Poly1305>(&ct, &nonce, &k)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn decrypt_bytes_accepts_non_utf8_plaintext() {
+        let k = create_new_key();
+        let (ct, nonce) = encrypt_bytes(&[0xc3, 0x28], &k).unwrap();
+
+        assert_eq!(decrypt_bytes(&ct, &nonce, &k).unwrap(), [0xc3, 0x28]);
+        assert!(matches!(
+            decrypt_string(&ct, &nonce, &k),
+            Err(ObfuscatorError::InvalidUtf8 { .. })
+        ));
+    }
+
+    #[test]
+    fn field_aad_is_length_prefixed() {
+        assert_ne!(field_aad("ab", "c"), field_aad("a", "bc"));
+    }
+
+    #[test]
+    fn envelope_authenticates_its_header() {
+        let k = create_new_key();
+        let sealed = Envelope::seal::<Aes256Gcm>(b"v", &[], 1, &k).unwrap();
+
+        let mut bytes = sealed.encode();
+        bytes[2] = 2;
+        let relabeled = Envelope::decode(&bytes).unwrap();
+
+        assert_eq!(relabeled.key_id(), 2);
+        assert!(relabeled.open(&k, &[]).is_err());
+        assert!(sealed.open(&k, b"other context").is_err());
+    }
+
+    #[test]
+    fn seal_display_and_open_parse_round_trip() {
+        let k = create_new_key();
+        let sealed = seal_display::<Aes256Gcm, _>(-9_000i64, &[], 3, &k).unwrap();
+
+        assert_eq!(
+            open_parse::<Aes256Gcm, i64>(&sealed, &[], &k).unwrap(),
+            -9_000
+        );
+    }
+
+    #[test]
+    fn try_default_key_matches_default_key() {
+        assert_eq!(
+            try_default_key().unwrap().as_bytes(),
+            default_key().as_bytes()
+        );
+    }
+
+    #[test]
+    fn key_from_shares_names_missing_share() {
+        let share = "00".repeat(KEY_LEN);
+        let salt = "00".repeat(SALT_LEN);
+
+        let err = key_from_shares(Some(&share), Some(&share), None, Some(&salt)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ObfuscatorError::MissingKeyShare {
+                share: "OBF_KEY_SHARE_C_HEX"
+            }
+        ));
+    }
+
+    #[test]
+    fn key_from_shares_names_malformed_share() {
+        let share = "00".repeat(KEY_LEN);
+
+        let err =
+            key_from_shares(Some(&share), Some("zz"), Some(&share), Some(&share)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            ObfuscatorError::MalformedKeyShare {
+                share: "OBF_KEY_SHARE_B_HEX"
+            }
+        ));
+        assert!(key_from_shares(Some(&share), Some(&share), Some(&share), Some(&share)).is_err());
+    }
+
+    #[test]
+    fn scalar_codec_round_trips_every_supported_type() {
+        fn round_trip<T: ScalarCodec + PartialEq + core::fmt::Debug + Copy>(values: &[T]) {
+            for value in values {
+                let bytes = value.to_le_bytes();
+                assert_eq!(
+                    <T as ScalarCodec>::from_le_bytes(bytes.as_ref()).unwrap(),
+                    *value
+                );
+            }
+        }
+
+        round_trip(&[u8::MIN, u8::MAX]);
+        round_trip(&[i16::MIN, -1, i16::MAX]);
+        round_trip(&[u32::MAX, 7]);
+        round_trip(&[i64::MIN, i64::MAX]);
+        round_trip(&[u128::MAX, i128::MAX as u128]);
+        round_trip(&[i128::MIN]);
+        round_trip(&[usize::MAX, 0]);
+        round_trip(&[isize::MIN, isize::MAX]);
+        round_trip(&[true, false]);
+        round_trip(&['a', '\u{10ffff}', 'é']);
+        round_trip(&[0.1f32, -0.0, f32::MAX, f32::INFINITY]);
+        round_trip(&[core::f64::consts::PI, f64::MIN_POSITIVE, f64::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn scalar_codec_is_fixed_width() {
+        assert_eq!(
+            7u64.to_le_bytes().len(),
+            9_000_000_000u64.to_le_bytes().len()
+        );
+        assert_eq!(ScalarCodec::to_le_bytes(&1u64), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(ScalarCodec::to_le_bytes(&true), [1]);
+        assert_eq!(ScalarCodec::to_le_bytes(&1usize).len(), 8);
+    }
+
+    #[test]
+    fn scalar_codec_rejects_invalid_encodings() {
+        assert!(matches!(
+            bool::from_le_bytes(&[2]),
+            Err(ObfuscatorError::ParseFailed { type_name: "bool" })
+        ));
+        assert!(char::from_le_bytes(&0xd800u32.to_le_bytes()).is_err());
+        assert!(<u32 as ScalarCodec>::from_le_bytes(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn nan_round_trips_bit_for_bit() {
+        let k = create_new_key();
+        let nan = f64::from_bits(0x7ff8_0000_dead_beef);
+        let (ct, nonce) = encrypt_scalar(nan, &k).unwrap();
+
+        assert_eq!(
+            decrypt_scalar::<f64>(&ct, &nonce, &k).unwrap().to_bits(),
+            nan.to_bits()
+        );
+    }
+
+    #[test]
+    fn negative_zero_and_char_envelopes_round_trip() {
+        let k = create_new_key();
+        let zero = seal_scalar::<Aes256Gcm, _>(-0.0f32, &[], 0, &k).unwrap();
+        let letter = seal_scalar::<Aes256Gcm, _>('ß', &[], 0, &k).unwrap();
+
+        assert_eq!(
+            open_scalar::<Aes256Gcm, f32>(&zero, &[], &k)
+                .unwrap()
+                .to_bits(),
+            (-0.0f32).to_bits()
+        );
+        assert_eq!(
+            open_scalar::<Aes256Gcm, char>(&letter, &[], &k).unwrap(),
+            'ß'
+        );
+    }
+
+    #[test]
+    fn scalar_ciphertext_length_does_not_depend_on_magnitude() {
+        let k = create_new_key();
+        let (small, _) = encrypt_scalar(7u64, &k).unwrap();
+        let (large, _) = encrypt_scalar(9_000_000_000u64, &k).unwrap();
+
+        assert_eq!(small.len(), large.len());
+    }
+
+    #[test]
+    fn open_scalar_reads_binary_and_text_envelopes() {
+        let k = create_new_key();
+        let binary = seal_scalar::<Aes256Gcm, _>(-42i32, b"f", 0, &k).unwrap();
+        assert_eq!(binary.version(), BINARY_SCALAR_VERSION);
+        assert_eq!(
+            open_scalar::<Aes256Gcm, i32>(&binary, b"f", &k).unwrap(),
+            -42
+        );
+
+        let mut text = seal_display::<Aes256Gcm, _>(-42i32, b"f", 0, &k).unwrap();
+        text.version = 1;
+        text.ciphertext = Aes256Gcm::encrypt(
+            &k,
+            &text.nonce.as_slice().try_into().unwrap(),
+            b"-42",
+            &text.full_aad(b"f"),
+        )
+        .unwrap();
+        assert_eq!(open_scalar::<Aes256Gcm, i32>(&text, b"f", &k).unwrap(), -42);
+
+        let (ct, nonce) = encrypt_display_with::<Aes256Gcm, _>(true, &k).unwrap();
+        let legacy = Envelope::from_legacy(ct, nonce);
+        assert!(open_scalar::<Aes256Gcm, bool>(&legacy, &[], &k).unwrap());
+    }
+
+    #[test]
+    fn padding_rounds_payload_up_to_its_bucket() {
+        assert_eq!(Padding::PowerOfTwo.padded_len(0), 4);
+        assert_eq!(Padding::PowerOfTwo.padded_len(1), 8);
+        assert_eq!(Padding::PowerOfTwo.padded_len(12), 16);
+        assert_eq!(Padding::PowerOfTwo.padded_len(13), 32);
+        assert_eq!(Padding::Bucket(32).padded_len(1), 32);
+        assert_eq!(Padding::Bucket(32).padded_len(29), 64);
+        assert_eq!(Padding::Bucket(0).padded_len(3), 7);
+    }
+
+    #[test]
+    fn pad_and_unpad_round_trip() {
+        for input in [&b""[..], b"a", b"hunter2", &[0u8; 40]] {
+            let padded = pad(input, Padding::Bucket(16)).unwrap();
+            assert_eq!(padded.len() % 16, 0);
+            assert_eq!(unpad(&padded).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn unpad_rejects_truncated_payloads() {
+        assert!(matches!(
+            unpad(&[1, 0]),
+            Err(ObfuscatorError::InvalidPadding)
+        ));
+        assert!(matches!(
+            unpad(&[9, 0, 0, 0, b'a']),
+            Err(ObfuscatorError::InvalidPadding)
+        ));
+    }
+
+    #[test]
+    fn padded_strings_in_one_bucket_have_equal_ciphertext_lengths() {
+        let k = create_new_key();
+        let (short, _) = encrypt_string_padded("on", Padding::PowerOfTwo, &k).unwrap();
+        let (long, nonce) = encrypt_string_padded("off", Padding::PowerOfTwo, &k).unwrap();
+
+        assert_eq!(short.len(), long.len());
+        assert_eq!(decrypt_string_padded(&long, &nonce, &k).unwrap(), "off");
+    }
+
+    #[test]
+    fn padded_envelope_round_trips() {
+        let k = create_new_key();
+        let sealed =
+            seal_string_padded::<ChaCha20Poly1305>("token", Padding::Bucket(64), b"f", 3, &k)
+                .unwrap();
+
+        assert_eq!(sealed.ciphertext().len(), 64 + ChaCha20Poly1305::TAG_LEN);
+        assert_eq!(
+            open_string_padded::<ChaCha20Poly1305>(&sealed, b"f", &k).unwrap(),
+            "token"
+        );
+    }
+
+    #[test]
+    fn decrypt_in_place_matches_decrypt() {
+        let k = create_new_key();
+        let nonce = [9u8; 12];
+        let sealed = ChaCha20Poly1305::encrypt(&k, &nonce, b"in place", b"ad").unwrap();
+        let (ciphertext, tag) = sealed.split_at(8);
+
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(ciphertext);
+        ChaCha20Poly1305::decrypt_in_place(&k, &nonce, &mut buffer, tag, b"ad").unwrap();
+        assert_eq!(&buffer, b"in place");
+
+        buffer.copy_from_slice(ciphertext);
+        assert!(matches!(
+            ChaCha20Poly1305::decrypt_in_place(&k, &nonce, &mut buffer, tag, b"other"),
+            Err(ObfuscatorError::TamperDetected)
+        ));
+        assert!(Aes256Gcm::decrypt_in_place(&k, &nonce, &mut buffer, &tag[..4], b"ad").is_err());
+    }
+
     #[test]
     fn decrypt_with_wrong_key_should_err() {
         let k_ok = Key(core::array::from_fn(|i| i as u8));
         let k_bad = Key([0u8; 32]);
         let (ct, nonce) = encrypt_string("secret", &k_ok).unwrap();
-        assert!(decrypt_string(&ct, &nonce, &k_bad).is_err());
+        assert!(matches!(
+            decrypt_string(&ct, &nonce, &k_bad),
+            Err(ObfuscatorError::TamperDetected)
+        ));
+    }
+
+    #[test]
+    fn modified_ciphertext_is_reported_as_tampering() {
+        let k = create_new_key();
+        let (mut ct, nonce) = encrypt_string("secret", &k).unwrap();
+        ct[0] ^= 1;
+
+        assert!(matches!(
+            decrypt_string(&ct, &nonce, &k),
+            Err(ObfuscatorError::TamperDetected)
+        ));
+    }
+
+    #[test]
+    fn malformed_hex_key_is_invalid_key_material() {
+        assert!(matches!(
+            Key::from_hex("not hex"),
+            Err(ObfuscatorError::InvalidKeyMaterial)
+        ));
     }
 }
@@ -1,23 +1,105 @@
-use std::{error, fmt, path::PathBuf};
+use alloc::string::String;
+use core::{error, fmt, str::Utf8Error};
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
 
 #[derive(Debug)]
 pub enum ObfuscatorError {
     EncryptionError,
-    InvalidFileExtension { path: PathBuf },
+    /// The cipher could not be set up to decrypt.
+    DecryptionFailed,
+    /// Authentication failed: the ciphertext, nonce, associated data or key
+    /// is not what the value was sealed with.
+    TamperDetected,
+    /// The value decrypted but is not valid UTF-8.
+    InvalidUtf8 {
+        source: Utf8Error,
+    },
+    /// The value decrypted but does not parse as the requested type.
+    ParseFailed {
+        type_name: &'static str,
+    },
+    /// Key bytes or hex of the wrong length or format.
+    InvalidKeyMaterial,
+    #[cfg(feature = "std")]
+    InvalidFileExtension {
+        path: PathBuf,
+    },
+    /// A file, such as a key file, could not be read.
+    #[cfg(feature = "std")]
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    KeyUnavailable {
+        reason: String,
+    },
+    UnknownKeyId {
+        key_id: u32,
+    },
+    InvalidEnvelope {
+        reason: String,
+    },
+    /// The length prefix of a padded value points past its end.
+    InvalidPadding,
+    MissingKeyShare {
+        share: &'static str,
+    },
+    MalformedKeyShare {
+        share: &'static str,
+    },
 }
 
 impl fmt::Display for ObfuscatorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ObfuscatorError::EncryptionError => write!(f, "Encryption failed :("),
+            ObfuscatorError::DecryptionFailed => write!(f, "Decryption failed"),
+            ObfuscatorError::TamperDetected => write!(
+                f,
+                "Authentication failed: ciphertext was tampered with or the key does not match"
+            ),
+            ObfuscatorError::InvalidUtf8 { .. } => write!(f, "Decrypted value is not valid UTF-8"),
+            ObfuscatorError::ParseFailed { type_name } => {
+                write!(f, "Decrypted value does not parse as {}", type_name)
+            }
+            ObfuscatorError::InvalidKeyMaterial => write!(f, "Invalid key material"),
+            #[cfg(feature = "std")]
             ObfuscatorError::InvalidFileExtension { path } => {
                 write!(f, "Invalid file extension for: {}", path.display())
             }
+            #[cfg(feature = "std")]
+            ObfuscatorError::Io { path, source } => {
+                write!(f, "Cannot read {}: {}", path.display(), source)
+            }
+            ObfuscatorError::KeyUnavailable { reason } => write!(f, "Key unavailable: {}", reason),
+            ObfuscatorError::UnknownKeyId { key_id } => {
+                write!(f, "No key with id {} in the keyring", key_id)
+            }
+            ObfuscatorError::InvalidEnvelope { reason } => {
+                write!(f, "Invalid ciphertext envelope: {}", reason)
+            }
+            ObfuscatorError::InvalidPadding => write!(f, "Invalid padding in decrypted value"),
+            ObfuscatorError::MissingKeyShare { share } => {
+                write!(f, "Build-time key share {} is missing", share)
+            }
+            ObfuscatorError::MalformedKeyShare { share } => {
+                write!(f, "Build-time key share {} is malformed", share)
+            }
         }
     }
 }
 
-impl error::Error for ObfuscatorError {}
+impl error::Error for ObfuscatorError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ObfuscatorError::InvalidUtf8 { source } => Some(source),
+            #[cfg(feature = "std")]
+            ObfuscatorError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -51,4 +133,73 @@ mod tests {
             displayed
         }
     }
+
+    #[test]
+    fn display_key_unavailable() {
+        let key_err = ObfuscatorError::KeyUnavailable {
+            reason: "environment variable APP_KEY is not set".to_string(),
+        };
+
+        let displayed = key_err.to_string();
+
+        assert! {
+            displayed.contains("APP_KEY"),
+            "Expected output contains: 'APP_KEY', got: {}",
+            displayed
+        }
+    }
+
+    #[test]
+    fn display_parse_failed_names_type() {
+        let parse_err = ObfuscatorError::ParseFailed { type_name: "u32" };
+
+        let displayed = parse_err.to_string();
+
+        assert! {
+            displayed.contains("u32"),
+            "Expected output contains: 'u32', got: {}",
+            displayed
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_chains_source() {
+        let source = String::from_utf8(vec![0xc3, 0x28])
+            .unwrap_err()
+            .utf8_error();
+        let utf8_err = ObfuscatorError::InvalidUtf8 { source };
+
+        let chained = error::Error::source(&utf8_err).expect("source must be set");
+
+        assert_eq!(chained.to_string(), source.to_string());
+        assert!(error::Error::source(&ObfuscatorError::TamperDetected).is_none());
+    }
+
+    #[test]
+    fn io_error_chains_source() {
+        let io_err = ObfuscatorError::Io {
+            path: PathBuf::from("app.key"),
+            source: io::Error::from(io::ErrorKind::PermissionDenied),
+        };
+
+        assert!(io_err.to_string().contains("app.key"));
+        let chained = error::Error::source(&io_err).expect("source must be set");
+        let chained = chained.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(chained.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn display_missing_key_share() {
+        let share_err = ObfuscatorError::MissingKeyShare {
+            share: "OBF_KEY_SHARE_B_HEX",
+        };
+
+        let displayed = share_err.to_string();
+
+        assert! {
+            displayed.contains("OBF_KEY_SHARE_B_HEX") && displayed.contains("missing"),
+            "Expected output names the missing share, got: {}",
+            displayed
+        }
+    }
 }
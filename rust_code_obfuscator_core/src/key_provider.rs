@@ -0,0 +1,377 @@
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use std::{env, fs, path::PathBuf};
+
+use crate::crypto::{try_default_key, Cipher, Envelope, Key};
+use crate::errors::ObfuscatorError;
+
+/// Source of the master key that `#[derive(Obfuscate)]` types and the crypto
+/// helpers seal values under.
+///
+/// The build-time key shares are only one option: a provider can also supply
+/// a key at runtime, so one binary can serve several deployments with their
+/// own keys. Subkeys are still derived per field from whatever master key the
+/// provider returns.
+///
+/// Values are sealed under [`master_key`](KeyProvider::master_key) and tagged
+/// with [`key_id`](KeyProvider::key_id); opening looks the key up again by the
+/// id recorded in the [`Envelope`], which is what makes rotation possible
+/// (see [`Keyring`]).
+pub trait KeyProvider {
+    fn master_key(&self) -> Result<Key, ObfuscatorError>;
+
+    /// Id stored alongside values sealed under [`master_key`](KeyProvider::master_key).
+    fn key_id(&self) -> u32 {
+        0
+    }
+
+    /// Key for a value sealed under `key_id`.
+    ///
+    /// Single-key providers only know their own id.
+    fn master_key_for(&self, key_id: u32) -> Result<Key, ObfuscatorError> {
+        if key_id == self.key_id() {
+            self.master_key()
+        } else {
+            Err(ObfuscatorError::UnknownKeyId { key_id })
+        }
+    }
+}
+
+impl<P: KeyProvider + ?Sized> KeyProvider for &P {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        (**self).master_key()
+    }
+
+    fn key_id(&self) -> u32 {
+        (**self).key_id()
+    }
+
+    fn master_key_for(&self, key_id: u32) -> Result<Key, ObfuscatorError> {
+        (**self).master_key_for(key_id)
+    }
+}
+
+impl KeyProvider for Key {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        Ok(self.clone())
+    }
+}
+
+/// The key reassembled from the build-time shares, see
+/// [`try_default_key`]. A missing or malformed share is reported as an error
+/// instead of falling back to a fixed key.
+///
+/// This is what `new_clear` / `get_clear` use.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildKeyProvider;
+
+impl KeyProvider for BuildKeyProvider {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        try_default_key()
+    }
+}
+
+/// Reads a 64-character hex key from an environment variable at runtime.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct EnvKeyProvider {
+    var: String,
+}
+
+#[cfg(feature = "std")]
+impl EnvKeyProvider {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl KeyProvider for EnvKeyProvider {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        let hex = env::var(&self.var).map_err(|_| ObfuscatorError::KeyUnavailable {
+            reason: format!("environment variable {} is not set", self.var),
+        })?;
+        Key::from_hex(hex.trim())
+    }
+}
+
+/// Reads a key file at runtime: either exactly 32 raw bytes or 64 hex
+/// characters (surrounding whitespace is ignored).
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct FileKeyProvider {
+    path: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileKeyProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl KeyProvider for FileKeyProvider {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        let bytes = fs::read(&self.path).map_err(|source| ObfuscatorError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+
+        if let Ok(raw) = <[u8; 32]>::try_from(bytes.as_slice()) {
+            return Ok(Key::from(raw));
+        }
+
+        let hex = core::str::from_utf8(&bytes).map_err(|_| ObfuscatorError::InvalidKeyMaterial)?;
+        Key::from_hex(hex.trim())
+    }
+}
+
+/// Asks a user callback for the key, e.g. to fetch it from a secret store.
+#[derive(Clone, Copy, Debug)]
+pub struct FnKeyProvider<F> {
+    callback: F,
+}
+
+impl<F> FnKeyProvider<F>
+where
+    F: Fn() -> Result<Key, ObfuscatorError>,
+{
+    pub fn new(callback: F) -> Self {
+        Self { callback }
+    }
+}
+
+impl<F> KeyProvider for FnKeyProvider<F>
+where
+    F: Fn() -> Result<Key, ObfuscatorError>,
+{
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        (self.callback)()
+    }
+}
+
+/// A set of master keys addressed by id, one of which is active.
+///
+/// New values are sealed under the active key; older values keep opening as
+/// long as the key they name is still in the ring. To rotate, add the new key,
+/// make it active and re-seal stored values at your own pace.
+///
+/// ```
+/// use rust_code_obfuscator_core::crypto::{DefaultCipher, Key};
+/// use rust_code_obfuscator_core::key_provider::Keyring;
+///
+/// let mut ring = Keyring::new(1, Key::from([1; 32]));
+/// let old = ring.seal::<DefaultCipher>(b"v1 data", b"").unwrap();
+///
+/// ring.insert(2, Key::from([2; 32]));
+/// ring.set_active(2).unwrap();
+///
+/// assert_eq!(ring.open(&old, b"").unwrap(), b"v1 data");
+/// assert_eq!(ring.seal::<DefaultCipher>(b"v2 data", b"").unwrap().key_id(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Keyring {
+    active: u32,
+    keys: Vec<(u32, Key)>,
+}
+
+impl Keyring {
+    pub fn new(active_id: u32, active_key: Key) -> Self {
+        Self {
+            active: active_id,
+            keys: vec![(active_id, active_key)],
+        }
+    }
+
+    /// Adds a key, replacing any existing key with the same id.
+    pub fn insert(&mut self, key_id: u32, key: Key) -> &mut Self {
+        match self.keys.iter_mut().find(|(id, _)| *id == key_id) {
+            Some(entry) => entry.1 = key,
+            None => self.keys.push((key_id, key)),
+        }
+        self
+    }
+
+    /// Builder-style [`insert`](Keyring::insert).
+    pub fn with_key(mut self, key_id: u32, key: Key) -> Self {
+        self.insert(key_id, key);
+        self
+    }
+
+    /// Makes `key_id` the key new values are sealed under.
+    pub fn set_active(&mut self, key_id: u32) -> Result<(), ObfuscatorError> {
+        if !self.contains(key_id) {
+            return Err(ObfuscatorError::UnknownKeyId { key_id });
+        }
+        self.active = key_id;
+        Ok(())
+    }
+
+    /// Drops a retired key. The active key cannot be removed.
+    pub fn remove(&mut self, key_id: u32) -> Option<Key> {
+        if key_id == self.active {
+            return None;
+        }
+        let index = self.keys.iter().position(|(id, _)| *id == key_id)?;
+        Some(self.keys.remove(index).1)
+    }
+
+    pub fn contains(&self, key_id: u32) -> bool {
+        self.keys.iter().any(|(id, _)| *id == key_id)
+    }
+
+    pub fn active_id(&self) -> u32 {
+        self.active
+    }
+
+    /// Seals `plaintext` under the active key, bound to `aad`.
+    pub fn seal<C: Cipher>(
+        &self,
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Envelope, ObfuscatorError> {
+        Envelope::seal::<C>(plaintext, aad, self.active, &self.master_key()?)
+    }
+
+    /// Opens an envelope with the key its header names.
+    pub fn open(&self, envelope: &Envelope, aad: &[u8]) -> Result<Vec<u8>, ObfuscatorError> {
+        envelope.open(&self.master_key_for(envelope.key_id())?, aad)
+    }
+}
+
+impl KeyProvider for Keyring {
+    fn master_key(&self) -> Result<Key, ObfuscatorError> {
+        self.master_key_for(self.active)
+    }
+
+    fn key_id(&self) -> u32 {
+        self.active
+    }
+
+    fn master_key_for(&self, key_id: u32) -> Result<Key, ObfuscatorError> {
+        self.keys
+            .iter()
+            .find(|(id, _)| *id == key_id)
+            .map(|(_, key)| key.clone())
+            .ok_or(ObfuscatorError::UnknownKeyId { key_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX_KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn expected_key() -> [u8; 32] {
+        core::array::from_fn(|i| i as u8)
+    }
+
+    #[test]
+    fn build_provider_returns_default_key() {
+        let key = BuildKeyProvider.master_key().unwrap();
+        assert_eq!(key.as_bytes(), crate::crypto::default_key().as_bytes());
+    }
+
+    #[test]
+    fn env_provider_reads_hex_key() {
+        env::set_var("RUSTFUSCATOR_TEST_ENV_KEY", format!("{HEX_KEY}\n"));
+
+        let key = EnvKeyProvider::new("RUSTFUSCATOR_TEST_ENV_KEY")
+            .master_key()
+            .unwrap();
+
+        assert_eq!(key.as_bytes(), &expected_key());
+    }
+
+    #[test]
+    fn env_provider_reports_missing_variable() {
+        let err = EnvKeyProvider::new("RUSTFUSCATOR_TEST_UNSET_KEY")
+            .master_key()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("RUSTFUSCATOR_TEST_UNSET_KEY"));
+    }
+
+    #[test]
+    fn file_provider_accepts_raw_and_hex_keys() {
+        let dir = env::temp_dir().join(format!("rustfuscator-key-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let raw_path = dir.join("raw.key");
+        let hex_path = dir.join("hex.key");
+        fs::write(&raw_path, expected_key()).unwrap();
+        fs::write(&hex_path, HEX_KEY).unwrap();
+
+        let raw = FileKeyProvider::new(&raw_path).master_key().unwrap();
+        let hex = FileKeyProvider::new(&hex_path).master_key().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(raw.as_bytes(), &expected_key());
+        assert_eq!(hex.as_bytes(), &expected_key());
+    }
+
+    #[test]
+    fn file_provider_reports_missing_file() {
+        let err = FileKeyProvider::new("/nonexistent/rustfuscator.key")
+            .master_key()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("/nonexistent/rustfuscator.key"));
+        assert!(matches!(
+            err,
+            ObfuscatorError::Io { ref source, .. } if source.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn single_key_providers_only_know_their_own_id() {
+        let key = Key::from(expected_key());
+
+        assert_eq!(key.key_id(), 0);
+        assert!(key.master_key_for(0).is_ok());
+        assert!(matches!(
+            key.master_key_for(1),
+            Err(ObfuscatorError::UnknownKeyId { key_id: 1 })
+        ));
+    }
+
+    #[test]
+    fn keyring_opens_values_sealed_under_retired_keys() {
+        use crate::crypto::Aes256Gcm;
+
+        let mut ring = Keyring::new(1, Key::from([1; 32]));
+        let old = ring.seal::<Aes256Gcm>(b"old", &[]).unwrap();
+
+        ring.insert(2, Key::from([2; 32]));
+        ring.set_active(2).unwrap();
+        let new = ring.seal::<Aes256Gcm>(b"new", &[]).unwrap();
+
+        assert_eq!(old.key_id(), 1);
+        assert_eq!(new.key_id(), 2);
+        assert_eq!(ring.open(&old, &[]).unwrap(), b"old");
+        assert_eq!(ring.open(&new, &[]).unwrap(), b"new");
+
+        assert!(ring.remove(2).is_none());
+        assert!(ring.remove(1).is_some());
+        assert!(matches!(
+            ring.open(&old, &[]),
+            Err(ObfuscatorError::UnknownKeyId { key_id: 1 })
+        ));
+    }
+
+    #[test]
+    fn keyring_rejects_activating_unknown_key() {
+        let mut ring = Keyring::new(1, Key::from([1; 32])).with_key(2, Key::from([2; 32]));
+
+        assert!(ring.set_active(2).is_ok());
+        assert!(ring.set_active(3).is_err());
+        assert_eq!(ring.active_id(), 2);
+    }
+
+    #[test]
+    fn fn_provider_calls_back() {
+        let provider = FnKeyProvider::new(|| Ok(Key::from(expected_key())));
+        assert_eq!(provider.master_key().unwrap().as_bytes(), &expected_key());
+    }
+}
@@ -1,10 +1,26 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod crypto;
 pub mod errors;
+pub mod key_provider;
 pub mod obfuscator;
+#[cfg(feature = "registry")]
+pub mod registry;
 pub mod utils;
 
 mod obfstr;
 pub use obfstr::ObfStr;
 
+mod obfbytes;
+pub use obfbytes::ObfBytes;
+
+mod secret;
+pub use secret::SecretStr;
+
+#[cfg(feature = "registry")]
+pub use registry::{prewarm, purge_cache};
+
 #[cfg(feature = "secure_zeroize")]
 pub use zeroize;
@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+#[derive(Copy, Clone)]
+pub struct ObfBytes {
+    /// Initialization function that returns the cached `'static` bytes.
+    ///
+    /// Generated by the `obfuscate_bytes!` macro expansion, like `ObfStr`.
+    init: fn() -> &'static [u8],
+}
+
+impl ObfBytes {
+    /// Creates a new `ObfBytes` from an initialization function.
+    ///
+    /// This is intended to be used only by the `obfuscate_bytes!` macro.
+    #[inline]
+    pub const fn new(init: fn() -> &'static [u8]) -> Self {
+        Self { init }
+    }
+
+    /// Returns the decrypted bytes as a `&'static [u8]`.
+    ///
+    /// The bytes are decrypted only once per call-site and then cached.
+    #[inline]
+    pub fn as_bytes(self) -> &'static [u8] {
+        (self.init)()
+    }
+
+    /// Copies the decrypted bytes into an owned `Vec<u8>`.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+// &[u8] ergonomics
+impl core::ops::Deref for ObfBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        (self.init)()
+    }
+}
+
+impl AsRef<[u8]> for ObfBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        (self.init)()
+    }
+}
+
+// Formatting
+impl fmt::Debug for ObfBytes {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Debug output matches `&[u8]` behavior
+        fmt::Debug::fmt((self.init)(), f)
+    }
+}
+
+// Comparisons
+impl PartialEq for ObfBytes {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        (self.init)() == (other.init)()
+    }
+}
+
+impl Eq for ObfBytes {}
+
+impl PartialEq<&[u8]> for ObfBytes {
+    #[inline]
+    fn eq(&self, other: &&[u8]) -> bool {
+        (self.init)() == *other
+    }
+}
+
+impl PartialEq<ObfBytes> for &[u8] {
+    #[inline]
+    fn eq(&self, other: &ObfBytes) -> bool {
+        *self == (other.init)()
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for ObfBytes {
+    #[inline]
+    fn eq(&self, other: &[u8; N]) -> bool {
+        (self.init)() == other.as_slice()
+    }
+}
+
+impl<const N: usize> PartialEq<&[u8; N]> for ObfBytes {
+    #[inline]
+    fn eq(&self, other: &&[u8; N]) -> bool {
+        (self.init)() == other.as_slice()
+    }
+}
+
+impl PartialEq<Vec<u8>> for ObfBytes {
+    #[inline]
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        (self.init)() == other.as_slice()
+    }
+}
+
+impl PartialEq<ObfBytes> for Vec<u8> {
+    #[inline]
+    fn eq(&self, other: &ObfBytes) -> bool {
+        self.as_slice() == (other.init)()
+    }
+}
+
+// Conversions
+impl From<ObfBytes> for Vec<u8> {
+    #[inline]
+    fn from(value: ObfBytes) -> Self {
+        value.into_vec()
+    }
+}
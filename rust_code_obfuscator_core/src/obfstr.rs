@@ -1,5 +1,11 @@
+use alloc::{borrow::ToOwned, string::String};
 use core::fmt;
 
+use crate::obfuscator::ct_bytes_eq;
+
+/// Decrypts a literal into a zeroized stack buffer and passes it on.
+type ScopedOpen = fn(&mut dyn FnMut(&str));
+
 #[derive(Copy, Clone)]
 pub struct ObfStr {
     /// Initialization function that returns the cached `'static` string.
@@ -7,6 +13,9 @@ pub struct ObfStr {
     /// The function typically contains a `OnceLock` + decryption logic,
     /// generated by the macro expansion.
     init: fn() -> &'static str,
+
+    /// Decrypts a fresh, uncached copy; set by `obfuscate_string!`.
+    scoped: Option<ScopedOpen>,
 }
 
 impl ObfStr {
@@ -15,7 +24,19 @@ impl ObfStr {
     /// This is intended to be used only by the `obfuscate_string!` macro.
     #[inline]
     pub const fn new(init: fn() -> &'static str) -> Self {
-        Self { init }
+        Self {
+            init,
+            scoped: None,
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub const fn __literal(init: fn() -> &'static str, scoped: ScopedOpen) -> Self {
+        Self {
+            init,
+            scoped: Some(scoped),
+        }
     }
 
     /// Returns the decrypted string as a `&'static str`.
@@ -34,8 +55,35 @@ impl ObfStr {
     pub fn into_string(self) -> String {
         self.as_str().to_owned()
     }
-}
 
+    /// Passes the decrypted string to `f` without going through the cache.
+    ///
+    /// The string is decrypted into a stack buffer that is zeroized when `f`
+    /// returns or panics, as with `with_obfuscated!`. This keeps the plaintext
+    /// out of memory only while the call site is never read through
+    /// [`as_str`](Self::as_str) or the other cached accessors.
+    pub fn with<R>(self, f: impl FnOnce(&str) -> R) -> R {
+        match self.scoped {
+            Some(scoped) => {
+                let mut f = Some(f);
+                let mut result = None;
+                scoped(&mut |clear| result = f.take().map(|f| f(clear)));
+                result.expect("the literal is opened exactly once")
+            }
+            None => f(self.as_str()),
+        }
+    }
+
+    /// Compares `other` with the obfuscated string in constant time.
+    ///
+    /// For `obfuscate_string!` call sites the literal is decrypted into a
+    /// zeroized stack buffer for the comparison, as with [`with`](Self::with),
+    /// and never cached. Only the length of `other` may leak through timing.
+    /// To compare without decrypting at all, use `obfuscate_eq!`.
+    pub fn ct_eq(self, other: &str) -> bool {
+        self.with(|clear| ct_bytes_eq(clear.as_bytes(), other.as_bytes()))
+    }
+}
 
 // &str ergonomics
 impl core::ops::Deref for ObfStr {
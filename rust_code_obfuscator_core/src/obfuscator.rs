@@ -1,15 +1,32 @@
+use alloc::{string::String, vec::Vec};
+
+use zeroize::Zeroizing;
+
+use crate::crypto::{
+    decrypt_bytes, decrypt_bytes_with_aad, decrypt_string, decrypt_string_padded, derive_subkey,
+    unpad, Cipher, DefaultCipher, DefaultNonce, Key, KeyContext,
+};
+
 #[macro_export]
 macro_rules! obfuscate_string {
-    ($s:literal) => {{
+    ($s:literal $(, $($pad:tt)+)?) => {{
+        static CELL: $crate::obfuscator::__LiteralCell<&'static str> =
+            $crate::obfuscator::__LiteralCell::new();
+        fn scoped(f: &mut dyn FnMut(&str)) {
+            $crate::obfuscator::__obfuscated_scoped!($crate, $s $(, $($pad)+)?).with(f)
+        }
         fn init() -> &'static str {
-            static CELL: ::std::sync::OnceLock<&'static str> = ::std::sync::OnceLock::new();
             *CELL.get_or_init(|| {
-                let decrypted: ::std::string::String = cryptify::encrypt_string!($s);
-                $crate::obfuscator::__verify_literal_round_trip($s, decrypted.as_str());
-                ::std::boxed::Box::leak(decrypted.into_boxed_str())
+                let mut cached: &'static str = "";
+                scoped(&mut |decrypted| {
+                    $crate::obfuscator::__verify_literal_round_trip($s, decrypted);
+                    cached = $crate::obfuscator::__Box::leak(decrypted.into());
+                });
+                cached
             })
         }
-        $crate::ObfStr::new(init)
+        $crate::__register_literal!(CELL, init, $s.len());
+        $crate::ObfStr::__literal(init, scoped)
     }};
     ($other:expr) => {
         compile_error!("obfuscate_string! only accepts string literals");
@@ -18,36 +35,264 @@ macro_rules! obfuscate_string {
 
 #[macro_export]
 macro_rules! obfuscate_str {
-    ($s:literal) => {{
-        $crate::obfuscate_string!($s).as_str()
+    ($s:literal $(, $($pad:tt)+)?) => {{
+        $crate::obfuscate_string!($s $(, $($pad)+)?).as_str()
     }};
     ($other:expr) => {
         compile_error!("obfuscate_str! only accepts string literals");
     };
 }
 
+/// Decrypts a string literal into a stack buffer, passes it to the closure
+/// and wipes the buffer before returning, including when the closure panics.
+///
+/// ```
+/// use rust_code_obfuscator_core::with_obfuscated;
+///
+/// let len = with_obfuscated!("db-password", |s: &str| s.len());
+/// assert_eq!(len, 11);
+/// ```
 #[macro_export]
-macro_rules! obfuscate_num {
-    (-$n:literal) => {{
-        let rustfuscator_value = -$n;
-        $crate::obfuscator::__obfuscate_num_value(
-            rustfuscator_value,
-            (::core::line!() as u64)
-                ^ ((::core::column!() as u64) << 32)
-                ^ 0xa24b_aed4_963e_e407u64,
-        )
+macro_rules! with_obfuscated {
+    ($s:literal, $f:expr $(,)?) => {
+        $crate::obfuscator::__obfuscated_scoped!($crate, $s).with($f)
+    };
+    ($($t:tt)*) => {
+        compile_error!("with_obfuscated! expects a string literal and a closure");
+    };
+}
+
+/// Builds a lazily decrypted `&'static [&'static str]` from string literals.
+///
+/// Every literal is sealed separately; the whole table is decrypted on first
+/// use and cached for the call site, like [`obfuscate_string!`].
+///
+/// ```
+/// use rust_code_obfuscator_core::obfuscate_array;
+///
+/// let reserved: &'static [&'static str] = obfuscate_array!["admin", "root"];
+/// assert!(reserved.contains(&"root"));
+/// ```
+#[macro_export]
+macro_rules! obfuscate_array {
+    ($($s:literal),* $(,)?) => {{
+        static CELL: $crate::obfuscator::__LiteralCell<&'static [&'static str]> =
+            $crate::obfuscator::__LiteralCell::new();
+        fn init() -> &'static [&'static str] {
+            *CELL.get_or_init(|| {
+                let items: $crate::obfuscator::__Box<[&'static str]> =
+                    $crate::obfuscator::__Box::new([$({
+                        let decrypted: $crate::obfuscator::__String =
+                            $crate::obfuscator::__obfuscated_literal!($crate, $s);
+                        $crate::obfuscator::__verify_literal_round_trip($s, decrypted.as_str());
+                        let item: &'static str =
+                            $crate::obfuscator::__Box::leak(decrypted.into_boxed_str());
+                        item
+                    }),*]);
+                $crate::obfuscator::__Box::leak(items)
+            })
+        }
+        $crate::__register_literal!(CELL, init, 0 $(+ $s.len())*);
+        init()
+    }};
+    ($($other:tt)*) => {
+        compile_error!("obfuscate_array! only accepts string literals");
+    };
+}
+
+/// Builds an [`ObfMap`](crate::obfuscator::ObfMap) from `"key" => value`
+/// entries. Keys are stored only as keyed hashes computed at compile time,
+/// so a lookup hashes the probe once and never decrypts a key. Values are
+/// ordinary expressions; wrap secret ones in [`obfuscate_string!`].
+///
+/// ```
+/// use rust_code_obfuscator_core::obfuscate_map;
+///
+/// let roles = obfuscate_map! { "admin" => 0u8, "root" => 1 };
+/// assert_eq!(roles.get("root"), Some(&1));
+/// assert_eq!(roles.get("guest"), None);
+/// ```
+#[macro_export]
+macro_rules! obfuscate_map {
+    ($($entries:tt)*) => {
+        $crate::obfuscator::__obfuscated_map!($crate, { $($entries)* })
+    };
+}
+
+/// Compares a string with a literal in constant time without decrypting the
+/// literal: the input is checked against a keyed hash computed at compile
+/// time, so the plaintext never appears in memory.
+///
+/// ```
+/// use rust_code_obfuscator_core::obfuscate_eq;
+///
+/// let user = String::from("admin");
+/// assert!(obfuscate_eq!(user, "admin"));
+/// assert!(!obfuscate_eq!("guest", "admin"));
+/// ```
+#[macro_export]
+macro_rules! obfuscate_eq {
+    ($input:expr, $s:literal $(,)?) => {
+        $crate::obfuscator::__obfuscated_digest!($crate, $s)
+            .matches(::core::convert::AsRef::<[u8]>::as_ref(&$input))
+    };
+    ($($t:tt)*) => {
+        compile_error!("obfuscate_eq! expects an expression and a string literal");
+    };
+}
+
+/// Like [`obfuscate_string!`], but decrypts on every call into an owned
+/// [`SecretStr`](crate::SecretStr) that is zeroized on drop instead of being
+/// cached for the lifetime of the process.
+#[macro_export]
+macro_rules! obfuscate_secret {
+    ($s:literal $(, $($pad:tt)+)?) => {
+        $crate::SecretStr::new($crate::obfuscator::__obfuscated_literal!(
+            $crate, $s $(, $($pad)+)?
+        ))
+    };
+    ($other:expr) => {
+        compile_error!("obfuscate_secret! only accepts string literals");
+    };
+}
+
+#[macro_export]
+macro_rules! obfuscate_bytes {
+    ($b:literal) => {{
+        static CELL: $crate::obfuscator::__LiteralCell<&'static [u8]> =
+            $crate::obfuscator::__LiteralCell::new();
+        fn init() -> &'static [u8] {
+            *CELL.get_or_init(|| {
+                let decrypted: $crate::obfuscator::__Vec<u8> =
+                    $crate::obfuscator::__obfuscated_bytes!($crate, $b);
+                $crate::obfuscator::__verify_literal_round_trip(&$b[..], decrypted.as_slice());
+                $crate::obfuscator::__Box::leak(decrypted.into_boxed_slice())
+            })
+        }
+        $crate::__register_literal!(CELL, init, $b.len());
+        $crate::ObfBytes::new(init)
+    }};
+    ($other:expr) => {
+        compile_error!("obfuscate_bytes! only accepts byte string literals");
+    };
+}
+
+/// Embeds a file like `include_str!`, but encrypted. The path is relative to
+/// the calling crate's `Cargo.toml`, and the file is decrypted chunk by chunk
+/// on first use.
+#[macro_export]
+macro_rules! obfuscate_include_str {
+    ($path:literal) => {{
+        static CELL: $crate::obfuscator::__LiteralCell<&'static str> =
+            $crate::obfuscator::__LiteralCell::new();
+        fn init() -> &'static str {
+            *CELL.get_or_init(|| {
+                let decrypted: $crate::obfuscator::__String =
+                    $crate::obfuscator::__obfuscated_include_str!($crate, $path);
+                $crate::obfuscator::__Box::leak(decrypted.into_boxed_str())
+            })
+        }
+        $crate::__register_literal!(
+            CELL,
+            init,
+            $crate::obfuscator::__obfuscated_include_len!($path)
+        );
+        $crate::ObfStr::new(init)
     }};
-    ($n:literal) => {{
-        let rustfuscator_value = $n;
-        $crate::obfuscator::__obfuscate_num_value(
-            rustfuscator_value,
-            (::core::line!() as u64)
-                ^ ((::core::column!() as u64) << 32)
-                ^ 0xa24b_aed4_963e_e407u64,
-        )
+    ($other:expr) => {
+        compile_error!("obfuscate_include_str! only accepts a string literal path");
+    };
+}
+
+/// Byte counterpart of [`obfuscate_include_str!`], like `include_bytes!`.
+#[macro_export]
+macro_rules! obfuscate_include_bytes {
+    ($path:literal) => {{
+        static CELL: $crate::obfuscator::__LiteralCell<&'static [u8]> =
+            $crate::obfuscator::__LiteralCell::new();
+        fn init() -> &'static [u8] {
+            *CELL.get_or_init(|| {
+                let decrypted: $crate::obfuscator::__Vec<u8> =
+                    $crate::obfuscator::__obfuscated_include_bytes!($crate, $path);
+                $crate::obfuscator::__Box::leak(decrypted.into_boxed_slice())
+            })
+        }
+        $crate::__register_literal!(
+            CELL,
+            init,
+            $crate::obfuscator::__obfuscated_include_len!($path)
+        );
+        $crate::ObfBytes::new(init)
     }};
+    ($other:expr) => {
+        compile_error!("obfuscate_include_bytes! only accepts a string literal path");
+    };
+}
+
+/// Submits a cached literal call site to the `registry` feature's
+/// inventory; expands to nothing without it.
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_literal {
+    ($cell:ident, $init:ident, $len:expr) => {
+        // File, line and length stay out of release builds.
+        #[cfg(debug_assertions)]
+        $crate::registry::__inventory::submit! {
+            $crate::registry::__Entry::new(
+                || {
+                    $init();
+                },
+                || unsafe { $cell.__purge() },
+            )
+            .__at(::core::file!(), ::core::line!(), $len)
+        }
+        #[cfg(not(debug_assertions))]
+        $crate::registry::__inventory::submit! {
+            $crate::registry::__Entry::new(
+                || {
+                    $init();
+                },
+                || unsafe { $cell.__purge() },
+            )
+        }
+    };
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_literal {
+    ($cell:ident, $init:ident, $len:expr) => {};
+}
+
+#[macro_export]
+macro_rules! obfuscate_num {
+    (-$n:literal) => {
+        $crate::obfuscator::__obfuscated_num!($crate, -$n)
+    };
+    ($n:literal) => {
+        $crate::obfuscator::__obfuscated_num!($crate, $n)
+    };
     ($($t:tt)*) => {
-        compile_error!("obfuscate_num! only accepts integer literals");
+        compile_error!("obfuscate_num! only accepts integer, float and char literals");
+    };
+}
+
+/// Rewrites integer arithmetic and bitwise operators into equivalent mixed
+/// boolean-arithmetic expressions, chosen at random per call site.
+///
+/// `+ - * & | ^` and unary `!` / `-` are rewritten with wrapping semantics, so
+/// the result equals the `wrapping_*` form of the expression for every
+/// primitive integer type. `depth = N` (1 to 4, default 2) sets how many times
+/// the identities are applied to their own output.
+#[macro_export]
+macro_rules! obfuscate_expr {
+    ($($t:tt)+) => {
+        $crate::obfuscator::__obfuscated_expr!($crate, $($t)+)
+    };
+    () => {
+        compile_error!("obfuscate_expr! expects an integer expression");
     };
 }
 
@@ -63,21 +308,423 @@ macro_rules! obfuscate_flow {
 
 #[macro_export]
 macro_rules! obfuscate_dummy_branch {
-    () => {{
-        let rustfuscator_seed =
-            ::core::line!() as u64 ^ ((::core::column!() as u64) << 32) ^ 0x9e37_79b9_7f4a_7c15u64;
-        if ::core::hint::black_box(rustfuscator_seed.rotate_left(13)) == 0 {
-            ::core::hint::black_box(rustfuscator_seed);
-        }
-    }};
+    () => {
+        $crate::obfuscator::__obfuscated_dummy_branch!()
+    };
     ($($t:tt)*) => {
         compile_error!("obfuscate_dummy_branch! does not accept arguments");
     };
 }
 
+#[doc(hidden)]
+pub use obfuscator_derive::{
+    __obfuscated_bytes, __obfuscated_include_bytes, __obfuscated_include_str, __obfuscated_literal,
+    __obfuscated_digest, __obfuscated_dummy_branch, __obfuscated_expr, __obfuscated_include_len,
+    __obfuscated_map, __obfuscated_num, __obfuscated_scoped,
+};
+
+// Paths used by the macro expansions, so they work in `no_std` crates too.
+#[doc(hidden)]
+pub use alloc::{boxed::Box as __Box, string::String as __String, vec::Vec as __Vec};
+
+/// Once cell behind each literal call site: `std::sync::OnceLock` with the
+/// `std` feature, a spin-based `Once` without it. With the `registry`
+/// feature it is a resettable atomic pointer instead, so the cache can be
+/// purged.
+#[doc(hidden)]
+pub struct __LiteralCell<T> {
+    #[cfg(all(feature = "std", not(feature = "registry")))]
+    inner: std::sync::OnceLock<T>,
+    #[cfg(not(feature = "std"))]
+    inner: spin::Once<T>,
+    #[cfg(feature = "registry")]
+    inner: core::sync::atomic::AtomicPtr<T>,
+    #[cfg(feature = "registry")]
+    init_lock: std::sync::Mutex<()>,
+}
+
+#[cfg(feature = "registry")]
+unsafe impl<T: Send + Sync> Sync for __LiteralCell<T> {}
+
+impl<T> __LiteralCell<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(all(feature = "std", not(feature = "registry")))]
+            inner: std::sync::OnceLock::new(),
+            #[cfg(not(feature = "std"))]
+            inner: spin::Once::new(),
+            #[cfg(feature = "registry")]
+            inner: core::sync::atomic::AtomicPtr::new(core::ptr::null_mut()),
+            #[cfg(feature = "registry")]
+            init_lock: std::sync::Mutex::new(()),
+        }
+    }
+
+    #[inline]
+    pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        #[cfg(all(feature = "std", not(feature = "registry")))]
+        {
+            self.inner.get_or_init(init)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.inner.call_once(init)
+        }
+        #[cfg(feature = "registry")]
+        {
+            use core::sync::atomic::Ordering;
+
+            let cached = self.inner.load(Ordering::Acquire);
+            if !cached.is_null() {
+                // SAFETY: non-null pointers come from `Box::into_raw` below and
+                // stay valid until `__purge`, whose caller guarantees that no
+                // reference handed out here is still in use.
+                return unsafe { &*cached };
+            }
+
+            let _guard = self
+                .init_lock
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            let mut cached = self.inner.load(Ordering::Acquire);
+            if cached.is_null() {
+                cached = __Box::into_raw(__Box::new(init()));
+                self.inner.store(cached, Ordering::Release);
+            }
+            // SAFETY: as above.
+            unsafe { &*cached }
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+impl<T: __PurgeLeaked> __LiteralCell<T> {
+    /// Resets the cell and wipes the value it held, if any.
+    ///
+    /// # Safety
+    ///
+    /// No reference obtained from [`get_or_init`](Self::get_or_init), nor
+    /// into the leaked value, may be used afterwards.
+    pub unsafe fn __purge(&self) {
+        let _guard = self
+            .init_lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let cached = self
+            .inner
+            .swap(core::ptr::null_mut(), core::sync::atomic::Ordering::AcqRel);
+        if !cached.is_null() {
+            __Box::from_raw(cached).__purge_leaked();
+        }
+    }
+}
+
+/// Cached literal values that were leaked with `Box::leak` and can be
+/// reclaimed by a registry purge.
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub trait __PurgeLeaked {
+    /// Zeroizes and frees the leaked allocation.
+    ///
+    /// # Safety
+    ///
+    /// `self` must come from `Box::leak` and must not be used afterwards.
+    unsafe fn __purge_leaked(self);
+}
+
+#[cfg(feature = "registry")]
+impl __PurgeLeaked for &'static [u8] {
+    unsafe fn __purge_leaked(self) {
+        use zeroize::Zeroize;
+
+        __Box::from_raw(self as *const [u8] as *mut [u8]).zeroize();
+    }
+}
+
+#[cfg(feature = "registry")]
+impl __PurgeLeaked for &'static [&'static str] {
+    unsafe fn __purge_leaked(self) {
+        for item in self {
+            item.__purge_leaked();
+        }
+        drop(__Box::from_raw(self as *const [&'static str] as *mut [&'static str]));
+    }
+}
+
+#[cfg(feature = "registry")]
+impl __PurgeLeaked for &'static str {
+    unsafe fn __purge_leaked(self) {
+        self.as_bytes().__purge_leaked();
+    }
+}
+
+/// Decrypts a literal sealed at compile time by `__obfuscated_literal!`.
+///
+/// The key is a per-call-site subkey derived from a master that the expansion
+/// stores as two XOR shares.
+#[doc(hidden)]
+pub fn __open_literal(
+    ciphertext: &[u8],
+    nonce: &DefaultNonce,
+    share_a: &[u8; 32],
+    share_b: &[u8; 32],
+    call_site: u64,
+) -> String {
+    let master = Key::__from_shares(share_a, share_b);
+    let subkey = derive_subkey(&master, &KeyContext::call_site(call_site));
+    decrypt_string(ciphertext, nonce, &subkey).expect("rustfuscator literal decryption failed")
+}
+
+/// Like [`__open_literal`], for literals sealed with `pad` / `pad = N`.
+#[doc(hidden)]
+pub fn __open_literal_padded(
+    ciphertext: &[u8],
+    nonce: &DefaultNonce,
+    share_a: &[u8; 32],
+    share_b: &[u8; 32],
+    call_site: u64,
+) -> String {
+    let master = Key::__from_shares(share_a, share_b);
+    let subkey = derive_subkey(&master, &KeyContext::call_site(call_site));
+    decrypt_string_padded(ciphertext, nonce, &subkey)
+        .expect("rustfuscator literal decryption failed")
+}
+
+/// Keyed hash of a literal computed by `__obfuscated_digest!`:
+/// HMAC-SHA256 under a per-call-site key.
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct __LiteralDigest {
+    key: &'static [u8; 32],
+    digest: &'static [u8; 32],
+}
+
+impl __LiteralDigest {
+    pub const fn new(key: &'static [u8; 32], digest: &'static [u8; 32]) -> Self {
+        Self { key, digest }
+    }
+
+    /// Checks `input` against the digest in constant time.
+    pub fn matches(&self, input: &[u8]) -> bool {
+        ct_bytes_eq(&keyed_hash(self.key, input), self.digest)
+    }
+}
+
+/// A static lookup table built by [`obfuscate_map!`] whose keys exist in the
+/// binary only as HMAC-SHA256 digests under a per-call-site key.
+///
+/// Construction is `const`, so a map can live in a `static`.
+pub struct ObfMap<V, const N: usize> {
+    key: &'static [u8; 32],
+    entries: [(&'static [u8; 32], V); N],
+}
+
+impl<V, const N: usize> ObfMap<V, N> {
+    #[doc(hidden)]
+    pub const fn __new(key: &'static [u8; 32], entries: [(&'static [u8; 32], V); N]) -> Self {
+        Self { key, entries }
+    }
+
+    /// Returns the value stored under `key`.
+    ///
+    /// `key` is hashed once and compared with every entry in constant time,
+    /// so the lookup takes the same time whichever entry matches.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        let probe = keyed_hash(self.key, key.as_bytes());
+        let mut found = None;
+        for (digest, value) in &self.entries {
+            if ct_bytes_eq(&probe, *digest) {
+                found = Some(value);
+            }
+        }
+        found
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Iterates over the values in declaration order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<V, const N: usize> core::fmt::Debug for ObfMap<V, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ObfMap").field("len", &N).finish_non_exhaustive()
+    }
+}
+
+fn keyed_hash(key: &[u8; 32], input: &[u8]) -> [u8; 32] {
+    use hkdf::hmac::{Hmac, Mac};
+
+    let mut mac =
+        <Hmac<sha2::Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(input);
+    mac.finalize().into_bytes().into()
+}
+
+/// Compares two byte strings without an early exit; only the lengths leak.
+pub(crate) fn ct_bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    core::hint::black_box(diff) == 0
+}
+
+/// A literal sealed by `__obfuscated_scoped!` whose clear text, including any
+/// padding, is `N` bytes.
+#[doc(hidden)]
+pub struct __ScopedLiteral<const N: usize> {
+    ciphertext: &'static [u8],
+    nonce: &'static DefaultNonce,
+    share_a: &'static [u8; 32],
+    share_b: &'static [u8; 32],
+    call_site: u64,
+    padded: bool,
+}
+
+impl<const N: usize> __ScopedLiteral<N> {
+    pub const fn new(
+        ciphertext: &'static [u8],
+        nonce: &'static DefaultNonce,
+        share_a: &'static [u8; 32],
+        share_b: &'static [u8; 32],
+        call_site: u64,
+    ) -> Self {
+        Self {
+            ciphertext,
+            nonce,
+            share_a,
+            share_b,
+            call_site,
+            padded: false,
+        }
+    }
+
+    /// Like [`new`](Self::new), for a literal sealed with its padding frame.
+    pub const fn new_padded(
+        ciphertext: &'static [u8],
+        nonce: &'static DefaultNonce,
+        share_a: &'static [u8; 32],
+        share_b: &'static [u8; 32],
+        call_site: u64,
+    ) -> Self {
+        let mut literal = Self::new(ciphertext, nonce, share_a, share_b, call_site);
+        literal.padded = true;
+        literal
+    }
+
+    /// Decrypts into a stack buffer that is zeroized when this returns or
+    /// unwinds.
+    pub fn with<R>(self, f: impl FnOnce(&str) -> R) -> R {
+        let master = Key::__from_shares(self.share_a, self.share_b);
+        let subkey = derive_subkey(&master, &KeyContext::call_site(self.call_site));
+        let (ciphertext, tag) = self.ciphertext.split_at(N);
+
+        let mut buffer = Zeroizing::new([0u8; N]);
+        buffer.copy_from_slice(ciphertext);
+        DefaultCipher::decrypt_in_place(&subkey, self.nonce, &mut buffer[..], tag, &[])
+            .expect("rustfuscator literal decryption failed");
+        let clear = if self.padded {
+            unpad(&buffer[..]).expect("rustfuscator literal padding is invalid")
+        } else {
+            &buffer[..]
+        };
+        f(core::str::from_utf8(clear).expect("rustfuscator literal is not valid UTF-8"))
+    }
+}
+
+/// Byte-string counterpart of [`__open_literal`], used by `obfuscate_bytes!`.
+#[doc(hidden)]
+pub fn __open_literal_bytes(
+    ciphertext: &[u8],
+    nonce: &DefaultNonce,
+    share_a: &[u8; 32],
+    share_b: &[u8; 32],
+    call_site: u64,
+) -> Vec<u8> {
+    let master = Key::__from_shares(share_a, share_b);
+    let subkey = derive_subkey(&master, &KeyContext::call_site(call_site));
+    decrypt_bytes(ciphertext, nonce, &subkey).expect("rustfuscator literal decryption failed")
+}
+
+/// Decrypts a file sealed by `__obfuscated_include_bytes!`.
+///
+/// Each chunk is bound to its position by [`__included_chunk_aad`] and is
+/// decrypted straight into the output, which is allocated once at its final
+/// size, so at most one chunk of clear text exists outside of it.
+#[doc(hidden)]
+pub fn __open_included_bytes(
+    chunks: &[(&[u8], &DefaultNonce)],
+    len: usize,
+    share_a: &[u8; 32],
+    share_b: &[u8; 32],
+    call_site: u64,
+) -> Vec<u8> {
+    let master = Key::__from_shares(share_a, share_b);
+    let subkey = derive_subkey(&master, &KeyContext::call_site(call_site));
+
+    let mut contents = Vec::with_capacity(len);
+    for (index, (ciphertext, nonce)) in chunks.iter().enumerate() {
+        let aad = __included_chunk_aad(index as u32, index + 1 == chunks.len());
+        let chunk = decrypt_bytes_with_aad::<DefaultCipher>(ciphertext, nonce, &aad, &subkey)
+            .expect("rustfuscator included file decryption failed");
+        contents.extend_from_slice(&chunk);
+        #[cfg(feature = "secure_zeroize")]
+        {
+            let mut chunk = chunk;
+            zeroize::Zeroize::zeroize(&mut chunk);
+        }
+    }
+    contents
+}
+
+/// UTF-8 counterpart of [`__open_included_bytes`], used by
+/// `obfuscate_include_str!`. The file was checked to be UTF-8 when it was sealed.
+#[doc(hidden)]
+pub fn __open_included_str(
+    chunks: &[(&[u8], &DefaultNonce)],
+    len: usize,
+    share_a: &[u8; 32],
+    share_b: &[u8; 32],
+    call_site: u64,
+) -> String {
+    String::from_utf8(__open_included_bytes(
+        chunks, len, share_a, share_b, call_site,
+    ))
+    .expect("rustfuscator included file is not valid UTF-8")
+}
+
+/// Associated data of chunk `index` of an included file: `index: u32 LE | last: u8`.
+#[doc(hidden)]
+pub fn __included_chunk_aad(index: u32, last: bool) -> [u8; 5] {
+    let mut aad = [0u8; 5];
+    aad[..4].copy_from_slice(&index.to_le_bytes());
+    aad[4] = last as u8;
+    aad
+}
+
 #[doc(hidden)]
 #[inline]
-pub fn __verify_literal_round_trip(original: &str, decrypted: &str) {
+pub fn __verify_literal_round_trip<T>(original: &T, decrypted: &T)
+where
+    T: ?Sized + PartialEq + core::fmt::Debug,
+{
     #[cfg(all(debug_assertions, feature = "verify_literals"))]
     {
         debug_assert_eq!(
@@ -92,37 +739,200 @@ pub fn __verify_literal_round_trip(original: &str, decrypted: &str) {
     }
 }
 
+/// Types `obfuscate_num!` can produce, rebuilt from the `u128` bit pattern
+/// the literal was encoded as.
+#[doc(hidden)]
+pub trait __NumBits: Copy {
+    fn __from_bits(bits: u128) -> Self;
+
+    /// Whether `self` is the whole literal rather than a truncation of it.
+    fn __holds(self, bits: u128) -> bool;
+}
+
+macro_rules! impl_num_bits {
+    ($($ty:ty as $wide:ty),* $(,)?) => {
+        $(
+            impl __NumBits for $ty {
+                #[inline]
+                fn __from_bits(bits: u128) -> Self {
+                    bits as $ty
+                }
+
+                #[inline]
+                fn __holds(self, bits: u128) -> bool {
+                    self as $wide as u128 == bits
+                }
+            }
+        )*
+    };
+}
+
+impl_num_bits!(
+    u8 as u128, u16 as u128, u32 as u128, u64 as u128, u128 as u128, usize as u128,
+    i8 as i128, i16 as i128, i32 as i128, i64 as i128, i128 as i128, isize as i128,
+);
+
+/// Wrapping primitives behind `obfuscate_expr!` expansions.
 #[doc(hidden)]
-pub trait __ObfuscateNum: Copy {
-    fn __obfuscate_num(self, seed: u64) -> Self;
+pub trait __Mba: Copy {
+    fn __add(self, other: Self) -> Self;
+    fn __sub(self, other: Self) -> Self;
+    fn __mul(self, other: Self) -> Self;
+    fn __and(self, other: Self) -> Self;
+    fn __or(self, other: Self) -> Self;
+    fn __xor(self, other: Self) -> Self;
+    fn __not(self) -> Self;
+    fn __neg(self) -> Self;
+    /// `1` of the same type as `self`.
+    fn __one(self) -> Self;
 }
 
-macro_rules! impl_obfuscate_num {
+macro_rules! impl_mba {
     ($($ty:ty),* $(,)?) => {
         $(
-            impl __ObfuscateNum for $ty {
+            impl __Mba for $ty {
+                #[inline]
+                fn __add(self, other: Self) -> Self {
+                    self.wrapping_add(other)
+                }
+
+                #[inline]
+                fn __sub(self, other: Self) -> Self {
+                    self.wrapping_sub(other)
+                }
+
+                #[inline]
+                fn __mul(self, other: Self) -> Self {
+                    self.wrapping_mul(other)
+                }
+
                 #[inline]
-                fn __obfuscate_num(self, seed: u64) -> Self {
-                    let mask = ::core::hint::black_box(seed as $ty);
-                    let offset = ::core::hint::black_box(mask.rotate_left(7));
-                    let encoded = (self ^ mask).wrapping_add(offset);
-                    ::core::hint::black_box(encoded.wrapping_sub(offset) ^ mask)
+                fn __and(self, other: Self) -> Self {
+                    self & other
+                }
+
+                #[inline]
+                fn __or(self, other: Self) -> Self {
+                    self | other
+                }
+
+                #[inline]
+                fn __xor(self, other: Self) -> Self {
+                    self ^ other
+                }
+
+                #[inline]
+                fn __not(self) -> Self {
+                    !self
+                }
+
+                #[inline]
+                fn __neg(self) -> Self {
+                    self.wrapping_neg()
+                }
+
+                #[inline]
+                fn __one(self) -> Self {
+                    1
                 }
             }
         )*
     };
 }
 
-impl_obfuscate_num!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_mba!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
 
+macro_rules! impl_indirect {
+    ($($name:ident($($arg:ident),*);)*) => {
+        $(
+            /// Reads `raw` back as a pointer to the function `_target` names.
+            ///
+            /// # Safety
+            ///
+            /// `raw` must be the address of that function, as produced by
+            /// casting it to `*const ()`. Only fn items and fn pointers both
+            /// implement `Fn` and cast to `*const ()`, so `F` is one of them.
+            #[doc(hidden)]
+            #[inline(always)]
+            pub unsafe fn $name<F, $($arg,)* R>(_target: F, raw: *const ()) -> fn($($arg),*) -> R
+            where
+                F: Fn($($arg),*) -> R,
+            {
+                core::mem::transmute::<*const (), fn($($arg),*) -> R>(raw)
+            }
+        )*
+    };
+}
+
+// Call sites rewritten by `#[obfuscate_calls]`, by number of arguments.
+impl_indirect! {
+    __indirect0();
+    __indirect1(A1);
+    __indirect2(A1, A2);
+    __indirect3(A1, A2, A3);
+    __indirect4(A1, A2, A3, A4);
+    __indirect5(A1, A2, A3, A4, A5);
+    __indirect6(A1, A2, A3, A4, A5, A6);
+    __indirect7(A1, A2, A3, A4, A5, A6, A7);
+    __indirect8(A1, A2, A3, A4, A5, A6, A7, A8);
+}
+
+// Floats are encoded as their `f64` bits in the low half and their `f32` bits
+// above them, so an unsuffixed literal keeps its exact value either way.
+impl __NumBits for f32 {
+    #[inline]
+    fn __from_bits(bits: u128) -> Self {
+        f32::from_bits((bits >> 64) as u32)
+    }
+
+    #[inline]
+    fn __holds(self, _bits: u128) -> bool {
+        true
+    }
+}
+
+impl __NumBits for f64 {
+    #[inline]
+    fn __from_bits(bits: u128) -> Self {
+        f64::from_bits(bits as u64)
+    }
+
+    #[inline]
+    fn __holds(self, _bits: u128) -> bool {
+        true
+    }
+}
+
+impl __NumBits for char {
+    #[inline]
+    fn __from_bits(bits: u128) -> Self {
+        char::from_u32(bits as u32).unwrap_or_default()
+    }
+
+    #[inline]
+    fn __holds(self, bits: u128) -> bool {
+        u32::from(self) as u128 == bits
+    }
+}
+
+/// Rebuilds a value decoded by an `obfuscate_num!` expansion. `_zero` is a
+/// zero literal of the same kind and suffix as the original literal, so `T`
+/// is inferred exactly as it would have been for the literal itself.
 #[doc(hidden)]
 #[inline]
-pub fn __obfuscate_num_value<T: __ObfuscateNum>(value: T, seed: u64) -> T {
-    value.__obfuscate_num(seed)
+pub fn __num_from_bits<T: __NumBits>(bits: u128, _zero: T) -> T {
+    let value = T::__from_bits(bits);
+    debug_assert!(
+        value.__holds(bits),
+        "obfuscate_num! literal out of range for its inferred type"
+    );
+    value
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn obfuscate_string_macro_round_trips_literal() {
         assert_eq!(
@@ -131,6 +941,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn obfuscate_string_macro_strips_padding() {
+        assert_eq!(crate::obfuscate_string!("padded", pad).as_str(), "padded");
+        assert_eq!(crate::obfuscate_str!("", pad = 32), "");
+    }
+
+    #[test]
+    fn included_chunks_open_in_order_and_reject_reordering() {
+        use crate::crypto::encrypt_bytes_with_aad;
+
+        let share_a = [3u8; 32];
+        let share_b = [5u8; 32];
+        let subkey = derive_subkey(
+            &Key::__from_shares(&share_a, &share_b),
+            &KeyContext::call_site(11),
+        );
+        let parts: [&[u8]; 3] = [b"first ", b"second ", b"third"];
+        let sealed: Vec<(Vec<u8>, DefaultNonce)> = parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                let aad = super::__included_chunk_aad(index as u32, index == 2);
+                encrypt_bytes_with_aad::<DefaultCipher>(part, &aad, &subkey).unwrap()
+            })
+            .collect();
+        let chunks: Vec<(&[u8], &DefaultNonce)> =
+            sealed.iter().map(|(ct, nonce)| (&ct[..], nonce)).collect();
+
+        assert_eq!(
+            super::__open_included_str(&chunks, 18, &share_a, &share_b, 11),
+            "first second third"
+        );
+
+        let reordered = [chunks[1], chunks[0], chunks[2]];
+        let result = std::panic::catch_unwind(|| {
+            super::__open_included_bytes(&reordered, 18, &share_a, &share_b, 11)
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn obfuscate_eq_matches_only_the_exact_literal() {
+        let input = String::from("admin");
+
+        assert!(crate::obfuscate_eq!(input, "admin"));
+        assert!(crate::obfuscate_eq!("admin", "admin"));
+        assert!(crate::obfuscate_eq!(b"", ""));
+        assert!(!crate::obfuscate_eq!("Admin", "admin"));
+        assert!(!crate::obfuscate_eq!("admin ", "admin"));
+        assert!(!crate::obfuscate_eq!("", "admin"));
+    }
+
+    #[test]
+    fn obfuscate_array_decrypts_once_per_call_site() {
+        fn reserved() -> &'static [&'static str] {
+            crate::obfuscate_array!["admin", "root", "", "h\u{e9}llo",]
+        }
+
+        assert_eq!(reserved(), ["admin", "root", "", "h\u{e9}llo"]);
+        assert_eq!(reserved().as_ptr(), reserved().as_ptr());
+        assert!(crate::obfuscate_array![].is_empty());
+    }
+
+    #[test]
+    fn obfuscate_map_looks_up_hashed_keys() {
+        let roles = crate::obfuscate_map! {
+            "admin" => 0u8,
+            "root" => 1,
+            "" => 2,
+        };
+
+        assert_eq!(roles.len(), 3);
+        assert_eq!(roles.get("admin"), Some(&0));
+        assert_eq!(roles.get("root"), Some(&1));
+        assert_eq!(roles.get(""), Some(&2));
+        assert_eq!(roles.get("Admin"), None);
+        assert!(!roles.contains_key("guest"));
+        assert_eq!(roles.values().copied().collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(format!("{roles:?}"), "ObfMap { len: 3, .. }");
+    }
+
+    #[test]
+    fn with_obfuscated_passes_the_literal_and_returns_the_result() {
+        let upper = crate::with_obfuscated!("scoped secret", |s: &str| s.to_uppercase());
+        let empty = crate::with_obfuscated!("", |s: &str| s.is_empty());
+
+        assert_eq!(upper, "SCOPED SECRET");
+        assert!(empty);
+    }
+
+    #[test]
+    fn with_obfuscated_propagates_panics_from_the_closure() {
+        let result = std::panic::catch_unwind(|| {
+            crate::with_obfuscated!("unwinding", |s: &str| -> usize {
+                panic!("closure saw {} bytes", s.len())
+            })
+        });
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn obfuscate_num_round_trips_unsigned_integer_literals() {
         assert_eq!(crate::obfuscate_num!(0u8), 0u8);
@@ -162,11 +1073,77 @@ mod tests {
         assert_eq!(crate::obfuscate_num!(-123isize), -123isize);
     }
 
+    #[test]
+    fn obfuscate_num_round_trips_float_literals_bit_for_bit() {
+        assert_eq!(crate::obfuscate_num!(0.875f64), 0.875f64);
+        assert_eq!(crate::obfuscate_num!(-1.5f32), -1.5f32);
+        assert_eq!(crate::obfuscate_num!(2.5), 2.5);
+        assert_eq!(
+            crate::obfuscate_num!(-0.0f64).to_bits(),
+            (-0.0f64).to_bits()
+        );
+        assert_eq!(
+            __num_from_bits(0x7fc0_1234u128 << 64, 0.0f32).to_bits(),
+            0x7fc0_1234
+        );
+        assert_eq!(
+            __num_from_bits(0xfff8_dead_beef_0001, 0.0f64).to_bits(),
+            0xfff8_dead_beef_0001
+        );
+    }
+
+    #[test]
+    fn obfuscate_num_round_trips_char_literals() {
+        assert_eq!(crate::obfuscate_num!('x'), 'x');
+        assert_eq!(crate::obfuscate_num!('\u{10ffff}'), '\u{10ffff}');
+        assert_eq!(crate::obfuscate_num!('€'), '€');
+    }
+
+    #[test]
+    fn obfuscate_num_infers_types_like_the_literal() {
+        let default_int = crate::obfuscate_num!(7);
+        let default_float = crate::obfuscate_num!(1.1);
+        let from_context: u16 = crate::obfuscate_num!(65_000);
+        let narrow: f32 = crate::obfuscate_num!(1.1);
+
+        assert_eq!(core::mem::size_of_val(&default_int), 4);
+        assert_eq!(default_float, 1.1f64);
+        assert_eq!(from_context, 65_000);
+        assert_eq!(narrow, 1.1f32);
+        assert_eq!(crate::obfuscate_num!(-128i8), i8::MIN);
+        assert_eq!(
+            crate::obfuscate_num!(-170141183460469231731687303715884105728i128),
+            i128::MIN
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn obfuscate_num_catches_truncated_unsuffixed_literals() {
+        let value: u8 = crate::obfuscate_num!(300);
+        let _ = value;
+    }
+
     #[test]
     fn dummy_branch_macro_is_safe_to_execute() {
         crate::obfuscate_dummy_branch!();
     }
 
+    #[test]
+    fn dummy_branch_call_sites_all_compile_and_run() {
+        // Each expansion picks its own predicate and dead code.
+        macro_rules! call_sites {
+            ($($n:literal)*) => {
+                $(
+                    let _ = $n;
+                    crate::obfuscate_dummy_branch!();
+                )*
+            };
+        }
+        call_sites!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31);
+    }
+
     #[cfg(all(debug_assertions, feature = "verify_literals"))]
     #[test]
     #[should_panic(expected = "rustfuscator literal verification failed")]
@@ -0,0 +1,87 @@
+//! Link-time registry of cached literal call sites, behind the `registry`
+//! feature.
+//!
+//! Every `obfuscate_string!`, `obfuscate_bytes!`, `obfuscate_include_str!`
+//! and `obfuscate_include_bytes!` expansion submits an entry here, so the
+//! cached plaintexts can be decrypted up front with [`prewarm`] or wiped with
+//! [`purge_cache`].
+
+pub use inventory as __inventory;
+
+/// A cached literal call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallSite {
+    /// Source file of the macro call, as reported by `file!()`.
+    pub file: &'static str,
+    /// Line of the macro call, as reported by `line!()`.
+    pub line: u32,
+    /// Plaintext length in bytes, without padding.
+    pub len: usize,
+}
+
+/// Registry entry submitted by each cached literal expansion.
+#[doc(hidden)]
+pub struct __Entry {
+    #[cfg(debug_assertions)]
+    site: Option<CallSite>,
+    prewarm: fn(),
+    purge: unsafe fn(),
+}
+
+impl __Entry {
+    pub const fn new(prewarm: fn(), purge: unsafe fn()) -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            site: None,
+            prewarm,
+            purge,
+        }
+    }
+
+    /// Records where the literal is. Only called by expansions in builds with
+    /// `debug_assertions`, and only kept if this crate has them too, so
+    /// release binaries carry no map of their literals.
+    #[cfg_attr(not(debug_assertions), allow(unused_variables, unused_mut))]
+    pub const fn __at(mut self, file: &'static str, line: u32, len: usize) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            self.site = Some(CallSite { file, line, len });
+        }
+        self
+    }
+}
+
+inventory::collect!(__Entry);
+
+/// Decrypts every registered literal into its cache, so first use does not
+/// pay for decryption.
+pub fn prewarm() {
+    for entry in inventory::iter::<__Entry> {
+        (entry.prewarm)();
+    }
+}
+
+/// Zeroizes and frees every cached plaintext and resets its call site, which
+/// decrypts again on next use.
+///
+/// # Safety
+///
+/// Cached accessors such as `ObfStr::as_str` and `ObfBytes::as_bytes` hand
+/// out `'static` references into the cache. No such reference may be alive,
+/// or be created concurrently on another thread, while this runs.
+pub unsafe fn purge_cache() {
+    for entry in inventory::iter::<__Entry> {
+        (entry.purge)();
+    }
+}
+
+/// Lists the registered call sites, for auditing what a binary obfuscates.
+///
+/// Only available in builds with `debug_assertions`; call sites expanded in
+/// crates built without them are not listed.
+#[cfg(debug_assertions)]
+pub fn call_sites() -> impl Iterator<Item = CallSite> {
+    inventory::iter::<__Entry>
+        .into_iter()
+        .filter_map(|entry| entry.site)
+}
@@ -0,0 +1,81 @@
+use alloc::string::String;
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A decrypted string that is owned by the caller and wiped when dropped.
+///
+/// Returned by `obfuscate_secret!`. Unlike `ObfStr`, nothing is cached: every
+/// macro call decrypts into a fresh buffer, and the buffer is zeroized as soon
+/// as the guard goes out of scope. Keep the guard short-lived and avoid
+/// copying the contents out of it.
+pub struct SecretStr {
+    value: String,
+}
+
+impl SecretStr {
+    /// Takes ownership of an already decrypted string.
+    #[inline]
+    pub fn new(value: String) -> Self {
+        Self { value }
+    }
+
+    /// Returns the decrypted string, valid while the guard is alive.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl Drop for SecretStr {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretStr {}
+
+impl From<String> for SecretStr {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+// &str ergonomics
+impl core::ops::Deref for SecretStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<str> for SecretStr {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+// Formatting: redacted, so secrets do not end up in logs by accident
+impl fmt::Debug for SecretStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretStr(<redacted>)")
+    }
+}
+
+// Comparisons
+impl PartialEq<str> for SecretStr {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<&str> for SecretStr {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
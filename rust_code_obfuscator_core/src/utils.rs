@@ -1,14 +1,60 @@
+#[cfg(feature = "std")]
 use rand::{rng, Rng};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering};
 
 const MIN_SUFF_VALUE: u32 = 1000;
 const MAX_SUFF_VALUE: u32 = 9999;
 
+/// Environment variable holding the obfuscation seed.
+///
+/// When set, every source of randomness (build-time key shares and salt,
+/// literal sealing, generated names) is derived from it, so two builds of the
+/// same source produce identical artifacts. When unset, fresh randomness is used.
+pub const SEED_ENV: &str = "OBFUSCATOR_SEED";
+
+#[cfg(feature = "std")]
+static SUFFIX_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the seed from [`SEED_ENV`], if set to a non-empty value.
+#[cfg(feature = "std")]
+pub fn obfuscation_seed() -> Option<String> {
+    std::env::var(SEED_ENV).ok().filter(|seed| !seed.is_empty())
+}
+
+/// Derives 32 bytes from `seed` for one use (`domain`) and one input (`context`).
+///
+/// Parts are length-prefixed so different splits never collide.
+pub fn seeded_bytes(seed: &str, domain: &str, context: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in [b"rustfuscator/seed/v1".as_slice(), seed.as_bytes(), domain.as_bytes(), context] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+#[cfg(feature = "std")]
 pub fn generate_obf_suffix() -> u32 {
+    if let Some(seed) = obfuscation_seed() {
+        let index = SUFFIX_COUNTER.fetch_add(1, Ordering::Relaxed);
+        return seeded_obf_suffix(&seed, &index.to_le_bytes());
+    }
+
     let mut rng = rng();
     let num: u32 = rng.random_range(MIN_SUFF_VALUE..=MAX_SUFF_VALUE);
     num
 }
 
+/// Deterministic suffix for `context` under `seed`, in the same range as
+/// [`generate_obf_suffix`].
+pub fn seeded_obf_suffix(seed: &str, context: &[u8]) -> u32 {
+    let bytes = seeded_bytes(seed, "suffix", context);
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    MIN_SUFF_VALUE + value % (MAX_SUFF_VALUE - MIN_SUFF_VALUE + 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,8 +82,8 @@ mod tests {
             suf.to_string().len(),
             expected_suff_len,
             "Generated suffix must be {} digits long, received {}",
-            expected_suff_len.to_string(),
-            suf.to_string()
+            expected_suff_len,
+            suf
         );
         assert!(
             (MIN_SUFF_VALUE..=MAX_SUFF_VALUE).contains(&suf),
@@ -47,4 +93,29 @@ mod tests {
             suf
         );
     }
+
+    #[test]
+    fn seeded_suffix_is_deterministic_and_in_range() {
+        let a = seeded_obf_suffix("release-42", b"login");
+        let b = seeded_obf_suffix("release-42", b"login");
+
+        assert_eq!(a, b);
+        assert!((MIN_SUFF_VALUE..=MAX_SUFF_VALUE).contains(&a));
+        assert_ne!(
+            seeded_bytes("release-42", "suffix", b"login"),
+            seeded_bytes("release-43", "suffix", b"login")
+        );
+    }
+
+    #[test]
+    fn seeded_bytes_separate_domains_and_contexts() {
+        assert_ne!(
+            seeded_bytes("seed", "suffix", b"x"),
+            seeded_bytes("seed", "literal", b"x")
+        );
+        assert_ne!(
+            seeded_bytes("seed", "ab", b"c"),
+            seeded_bytes("seed", "a", b"bc")
+        );
+    }
 }
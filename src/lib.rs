@@ -1,3 +1,5 @@
+#![no_std]
+
 pub use rust_code_obfuscator_core::*;
 pub use obfuscator_derive::*;
 
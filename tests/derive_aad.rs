@@ -0,0 +1,71 @@
+use rust_code_obfuscator::crypto::{default_key, derive_subkey, field_aad, KeyContext};
+use rust_code_obfuscator::errors::ObfuscatorError;
+use rust_code_obfuscator::Obfuscate;
+
+#[derive(Debug, PartialEq, Obfuscate)]
+struct ApiSession {
+    username: String,
+    token: String,
+}
+
+#[derive(Debug, PartialEq, Obfuscate)]
+#[obfuscator(aad = false)]
+struct UnboundSession {
+    token: String,
+}
+
+#[test]
+fn derive_obfuscate_rejects_transplanted_field() {
+    let alice = ObfuscatedApiSession::new_clear("alice", "alice-token");
+    let mut bob = ObfuscatedApiSession::new_clear("bob", "bob-token");
+
+    bob.username = alice.token.clone();
+
+    assert!(matches!(
+        bob.get_clear_with(&default_key()),
+        Err(ObfuscatorError::TamperDetected)
+    ));
+}
+
+#[test]
+fn derive_obfuscate_rejects_reordered_fields() {
+    let mut session = ObfuscatedApiSession::new_clear("alice", "alice-token");
+
+    std::mem::swap(&mut session.username, &mut session.token);
+
+    assert!(session.get_clear_with(&default_key()).is_err());
+}
+
+#[test]
+fn derive_obfuscate_binds_fields_to_struct_and_field_name() {
+    let session = ObfuscatedApiSession::new_clear("alice", "alice-token");
+    let subkey = derive_subkey(
+        &default_key(),
+        &KeyContext::field(module_path!(), "ApiSession", "token"),
+    );
+
+    assert_eq!(
+        session
+            .token
+            .open(&subkey, &field_aad("ApiSession", "token"))
+            .unwrap(),
+        b"alice-token"
+    );
+    assert!(session.token.open(&subkey, &[]).is_err());
+    assert!(session
+        .token
+        .open(&subkey, &field_aad("ApiSession", "username"))
+        .is_err());
+}
+
+#[test]
+fn derive_obfuscate_can_opt_out_of_field_binding() {
+    let session = ObfuscatedUnboundSession::new_clear("unbound");
+    let subkey = derive_subkey(
+        &default_key(),
+        &KeyContext::field(module_path!(), "UnboundSession", "token"),
+    );
+
+    assert_eq!(session.get_clear().token, "unbound");
+    assert_eq!(session.token.open(&subkey, &[]).unwrap(), b"unbound");
+}
@@ -0,0 +1,48 @@
+use rust_code_obfuscator::crypto::{Aes256Gcm, ChaCha20Poly1305, Cipher};
+use rust_code_obfuscator::Obfuscate;
+
+#[derive(Debug, PartialEq, Obfuscate)]
+#[obfuscator(cipher = rust_code_obfuscator::crypto::ChaCha20Poly1305)]
+struct ChaChaFixture {
+    label: String,
+    limit: u32,
+}
+
+#[derive(Debug, PartialEq, Obfuscate)]
+#[obfuscator(cipher = Aes256Gcm)]
+struct AesFixture {
+    label: String,
+    enabled: bool,
+}
+
+#[test]
+fn derive_obfuscate_round_trips_with_selected_cipher() {
+    let chacha = ObfuscatedChaChaFixture::new_clear("embedded", 64);
+    assert_eq!(
+        chacha.get_clear(),
+        ChaChaFixture {
+            label: "embedded".to_string(),
+            limit: 64,
+        }
+    );
+
+    let aes = ObfuscatedAesFixture::new_clear("server", true);
+    assert_eq!(
+        aes.get_clear(),
+        AesFixture {
+            label: "server".to_string(),
+            enabled: true,
+        }
+    );
+}
+
+#[test]
+fn derive_obfuscate_stores_nonce_of_selected_cipher() {
+    let chacha = ObfuscatedChaChaFixture::new_clear("embedded", 64);
+    assert_eq!(chacha.label.algorithm(), ChaCha20Poly1305::ID);
+    assert_eq!(chacha.label.nonce().len(), ChaCha20Poly1305::NONCE_LEN);
+    assert_eq!(
+        chacha.label.ciphertext().len(),
+        "embedded".len() + ChaCha20Poly1305::TAG_LEN
+    );
+}
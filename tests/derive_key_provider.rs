@@ -0,0 +1,51 @@
+use rust_code_obfuscator::crypto::Key;
+use rust_code_obfuscator::errors::ObfuscatorError;
+use rust_code_obfuscator::key_provider::{BuildKeyProvider, FnKeyProvider};
+use rust_code_obfuscator::Obfuscate;
+
+#[derive(Debug, PartialEq, Obfuscate)]
+struct CustomerSecret {
+    api_key: String,
+    tier: u8,
+}
+
+fn customer_key(seed: u8) -> Key {
+    Key::from([seed; 32])
+}
+
+#[test]
+fn derive_obfuscate_round_trips_with_runtime_key() {
+    let key = customer_key(7);
+    let sealed = ObfuscatedCustomerSecret::new_clear_with(&key, "customer-a", 3).unwrap();
+
+    assert_eq!(
+        sealed.get_clear_with(&key).unwrap(),
+        CustomerSecret {
+            api_key: "customer-a".to_string(),
+            tier: 3,
+        }
+    );
+}
+
+#[test]
+fn derive_obfuscate_rejects_other_customer_key() {
+    let sealed =
+        ObfuscatedCustomerSecret::new_clear_with(&customer_key(1), "customer-a", 3).unwrap();
+
+    assert!(sealed.get_clear_with(&customer_key(2)).is_err());
+    assert!(sealed.get_clear_with(&BuildKeyProvider).is_err());
+}
+
+#[test]
+fn derive_obfuscate_accepts_callback_provider_and_propagates_its_errors() {
+    let provider = FnKeyProvider::new(|| Ok(customer_key(9)));
+    let sealed = ObfuscatedCustomerSecret::new_clear_with(&provider, "customer-b", 1).unwrap();
+    assert_eq!(sealed.get_clear_with(&provider).unwrap().tier, 1);
+
+    let failing = FnKeyProvider::new(|| {
+        Err(ObfuscatorError::KeyUnavailable {
+            reason: "vault offline".to_string(),
+        })
+    });
+    assert!(ObfuscatedCustomerSecret::new_clear_with(&failing, "customer-b", 1).is_err());
+}
@@ -0,0 +1,73 @@
+use rust_code_obfuscator::crypto::{Envelope, Key};
+use rust_code_obfuscator::errors::ObfuscatorError;
+use rust_code_obfuscator::key_provider::Keyring;
+use rust_code_obfuscator::Obfuscate;
+
+#[derive(Debug, PartialEq, Obfuscate)]
+struct StoredToken {
+    token: String,
+    version: u16,
+}
+
+fn keyring_v1() -> Keyring {
+    Keyring::new(1, Key::from([1; 32]))
+}
+
+fn keyring_v2() -> Keyring {
+    let mut ring = keyring_v1().with_key(2, Key::from([2; 32]));
+    ring.set_active(2).unwrap();
+    ring
+}
+
+#[test]
+fn derive_obfuscate_records_active_key_id() {
+    let sealed = ObfuscatedStoredToken::new_clear_with(&keyring_v2(), "t", 1).unwrap();
+
+    assert_eq!(sealed.token.key_id(), 2);
+    assert_eq!(sealed.version.key_id(), 2);
+}
+
+#[test]
+fn derive_obfuscate_opens_values_sealed_before_rotation() {
+    let old = ObfuscatedStoredToken::new_clear_with(&keyring_v1(), "before", 1).unwrap();
+    let ring = keyring_v2();
+
+    let clear = old.get_clear_with(&ring).unwrap();
+    let resealed =
+        ObfuscatedStoredToken::new_clear_with(&ring, &clear.token, clear.version).unwrap();
+
+    assert_eq!(
+        clear,
+        StoredToken {
+            token: "before".to_string(),
+            version: 1,
+        }
+    );
+    assert_eq!(resealed.token.key_id(), 2);
+    assert_eq!(resealed.get_clear_with(&ring).unwrap(), clear);
+}
+
+#[test]
+fn derive_obfuscate_reports_retired_key_id() {
+    let old = ObfuscatedStoredToken::new_clear_with(&keyring_v1(), "before", 1).unwrap();
+    let mut ring = keyring_v2();
+    ring.remove(1);
+
+    assert!(matches!(
+        old.get_clear_with(&ring),
+        Err(ObfuscatorError::UnknownKeyId { key_id: 1 })
+    ));
+}
+
+#[test]
+fn derive_obfuscate_fields_survive_encoding() {
+    let ring = keyring_v1();
+    let sealed = ObfuscatedStoredToken::new_clear_with(&ring, "persisted", 4).unwrap();
+
+    let restored = ObfuscatedStoredToken {
+        token: Envelope::decode(&sealed.token.encode()).unwrap(),
+        version: Envelope::decode(&sealed.version.encode()).unwrap(),
+    };
+
+    assert_eq!(restored.get_clear_with(&ring).unwrap().token, "persisted");
+}
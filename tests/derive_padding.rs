@@ -0,0 +1,57 @@
+use rust_code_obfuscator::crypto::{Cipher, DefaultCipher};
+use rust_code_obfuscator::{obfuscate_str, obfuscate_string, Obfuscate};
+
+#[derive(Debug, PartialEq, Obfuscate)]
+#[obfuscator(pad)]
+struct FeatureFlags {
+    flag: String,
+    rollout: u8,
+}
+
+#[derive(Debug, PartialEq, Obfuscate)]
+#[obfuscator(pad = 64)]
+struct Credentials {
+    password: String,
+}
+
+#[test]
+fn derive_obfuscate_pads_strings_to_power_of_two_buckets() {
+    let short = ObfuscatedFeatureFlags::new_clear("on", 10);
+    let long = ObfuscatedFeatureFlags::new_clear("beta", 90);
+
+    assert_eq!(short.flag.ciphertext().len(), long.flag.ciphertext().len());
+    assert_eq!(short.flag.ciphertext().len(), 8 + DefaultCipher::TAG_LEN);
+    assert_eq!(
+        long.get_clear(),
+        FeatureFlags {
+            flag: "beta".to_string(),
+            rollout: 90,
+        }
+    );
+}
+
+#[test]
+fn derive_obfuscate_pads_strings_to_configured_buckets() {
+    let short = ObfuscatedCredentials::new_clear("pw");
+    let long = ObfuscatedCredentials::new_clear("correct horse battery staple");
+
+    assert_eq!(
+        short.password.ciphertext().len(),
+        64 + DefaultCipher::TAG_LEN
+    );
+    assert_eq!(
+        short.password.ciphertext().len(),
+        long.password.ciphertext().len()
+    );
+    assert_eq!(long.get_clear().password, "correct horse battery staple");
+}
+
+#[test]
+fn padded_literal_macros_return_unpadded_strings() {
+    let flag = obfuscate_string!("debug", pad);
+    let token = obfuscate_str!("tok_123", pad = 128);
+
+    assert_eq!(flag, "debug");
+    assert_eq!(flag.len(), 5);
+    assert_eq!(token, "tok_123");
+}
@@ -1,3 +1,4 @@
+use rust_code_obfuscator::crypto::{default_key, Cipher, DefaultCipher};
 use rust_code_obfuscator::Obfuscate;
 
 #[derive(Debug, PartialEq, Obfuscate)]
@@ -14,6 +15,13 @@ struct ScalarFixture {
     signed_pointer_sized: isize,
 }
 
+#[derive(Debug, Obfuscate)]
+struct FloatCharFixture {
+    price: f64,
+    ratio: f32,
+    separator: char,
+}
+
 #[derive(Debug, PartialEq, Obfuscate)]
 struct QualifiedStringFixture {
     standard: std::string::String,
@@ -53,6 +61,24 @@ fn derive_obfuscate_round_trips_supported_scalar_fields() {
     );
 }
 
+#[test]
+fn derive_obfuscate_round_trips_float_and_char_fields_bit_for_bit() {
+    let nan = f64::from_bits(0x7ff8_0000_0000_beef);
+    let obfuscated = ObfuscatedFloatCharFixture::new_clear(nan, -0.0, '€');
+
+    let clear = obfuscated.get_clear();
+
+    assert_eq!(clear.price.to_bits(), nan.to_bits());
+    assert_eq!(clear.ratio.to_bits(), (-0.0f32).to_bits());
+    assert_eq!(clear.separator, '€');
+    assert_eq!(
+        ObfuscatedFloatCharFixture::new_clear(0.875, f32::MAX, 'x')
+            .get_clear()
+            .price,
+        0.875
+    );
+}
+
 #[test]
 fn derive_obfuscate_accepts_qualified_string_fields() {
     let obfuscated = ObfuscatedQualifiedStringFixture::new_clear("qualified");
@@ -64,3 +90,38 @@ fn derive_obfuscate_accepts_qualified_string_fields() {
         }
     );
 }
+
+#[test]
+fn derive_obfuscate_seals_fields_under_subkeys_of_the_build_key() {
+    let obfuscated = ObfuscatedQualifiedStringFixture::new_clear("qualified");
+
+    assert!(obfuscated.standard.open(&default_key(), &[]).is_err());
+}
+
+#[test]
+fn derive_obfuscate_seals_scalars_at_fixed_width() {
+    let small = ObfuscatedScalarFixture::new_clear("", false, 0, 0, 7, 0, 0, 0, 0, 0);
+    let large = ObfuscatedScalarFixture::new_clear(
+        "",
+        true,
+        u8::MAX,
+        i32::MIN,
+        9_000_000_000,
+        u128::MAX,
+        usize::MAX,
+        i64::MIN,
+        i128::MIN,
+        isize::MIN,
+    );
+
+    assert_eq!(small.large.ciphertext().len(), 8 + DefaultCipher::TAG_LEN);
+    assert_eq!(
+        small.large.ciphertext().len(),
+        large.large.ciphertext().len()
+    );
+    assert_eq!(
+        small.massive.ciphertext().len(),
+        large.massive.ciphertext().len()
+    );
+    assert_eq!(small.enabled.ciphertext().len(), 1 + DefaultCipher::TAG_LEN);
+}
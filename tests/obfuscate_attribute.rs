@@ -0,0 +1,130 @@
+use rust_code_obfuscator::obfuscate;
+
+#[obfuscate]
+fn classify(code: i32) -> &'static str {
+    const LIMIT: usize = 3;
+    let table = [0u8; LIMIT];
+
+    match code {
+        -128 => "min",
+        0 => "zero",
+        1..=9 if table.len() == LIMIT => "digit",
+        _ if code < -1 => "negative",
+        _ => {
+            if code % 2 == 0 {
+                "even"
+            } else {
+                "odd"
+            }
+        }
+    }
+}
+
+#[obfuscate(strings)]
+fn greeting(name: &str) -> String {
+    let mut out = String::from("hello, ");
+    out.push_str(name);
+    out.push_str("!");
+    out
+}
+
+#[obfuscate(nums, flow)]
+fn checksum(data: &[u8]) -> u32 {
+    let mut sum = 0x811c_9dc5u32;
+    for byte in data {
+        sum ^= u32::from(*byte);
+        sum = sum.wrapping_mul(0x0100_0193);
+    }
+    let mut rounds = 0;
+    while rounds < 2 {
+        sum = sum.rotate_left(5);
+        rounds += 1;
+    }
+    loop {
+        if sum != 0 {
+            break sum;
+        }
+        sum = -1i64 as u32;
+    }
+}
+
+struct Account {
+    balance: i64,
+}
+
+#[obfuscate(strings, nums)]
+impl Account {
+    const FEE: i64 = 2;
+
+    fn withdraw(&mut self, amount: i64) -> Result<i64, &'static str> {
+        if amount <= 0 {
+            return Err("invalid amount");
+        }
+        if self.balance - amount - Self::FEE < -100 {
+            return Err("overdraft limit reached");
+        }
+        self.balance -= amount + Self::FEE;
+        Ok(self.balance)
+    }
+
+    const fn limit() -> i64 {
+        -100
+    }
+}
+
+#[obfuscate]
+mod licensing {
+    pub static PRODUCT: &str = "rustfuscator";
+
+    #[derive(Debug, PartialEq)]
+    pub enum Tier {
+        Free = 1,
+        Pro = 2,
+    }
+
+    pub fn tier(key: &str) -> Tier {
+        if key.starts_with("PRO-") && key.len() > 8 {
+            Tier::Pro
+        } else {
+            Tier::Free
+        }
+    }
+}
+
+#[test]
+fn obfuscate_attribute_keeps_fn_behaviour() {
+    assert_eq!(classify(-128), "min");
+    assert_eq!(classify(0), "zero");
+    assert_eq!(classify(7), "digit");
+    assert_eq!(classify(-5), "negative");
+    assert_eq!(classify(12), "even");
+    assert_eq!(classify(13), "odd");
+    assert_eq!(greeting("world"), "hello, world!");
+}
+
+#[test]
+fn obfuscate_attribute_keeps_integer_types_and_values() {
+    let mut expected = 0x811c_9dc5u32;
+    for byte in b"abc" {
+        expected ^= u32::from(*byte);
+        expected = expected.wrapping_mul(0x0100_0193);
+    }
+    expected = expected.rotate_left(10);
+
+    assert_eq!(checksum(b"abc"), expected);
+}
+
+#[test]
+fn obfuscate_attribute_rewrites_impl_blocks_and_modules() {
+    let mut account = Account { balance: 10 };
+
+    assert_eq!(account.withdraw(0), Err("invalid amount"));
+    assert_eq!(account.withdraw(5), Ok(3));
+    assert_eq!(account.withdraw(200), Err("overdraft limit reached"));
+    assert_eq!(Account::limit(), -100);
+
+    assert_eq!(licensing::PRODUCT, "rustfuscator");
+    assert_eq!(licensing::tier("PRO-123456"), licensing::Tier::Pro);
+    assert_eq!(licensing::tier("PRO-1"), licensing::Tier::Free);
+    assert_eq!(licensing::Tier::Pro as i32, 2);
+}
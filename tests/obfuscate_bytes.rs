@@ -0,0 +1,38 @@
+use rust_code_obfuscator::{obfuscate_bytes, ObfBytes};
+
+const PINNED_CERT: &[u8] =
+    b"-----BEGIN CERTIFICATE-----\nMIIBszCCAVmgAwIBAgIU\n-----END CERTIFICATE-----\n";
+
+fn pinned_cert() -> ObfBytes {
+    obfuscate_bytes!(
+        b"-----BEGIN CERTIFICATE-----\nMIIBszCCAVmgAwIBAgIU\n-----END CERTIFICATE-----\n"
+    )
+}
+
+#[test]
+fn obfuscate_bytes_round_trips_from_public_crate() {
+    let cert = pinned_cert();
+
+    assert_eq!(cert.as_bytes(), PINNED_CERT);
+    assert_eq!(cert, PINNED_CERT);
+    assert_eq!(cert.len(), PINNED_CERT.len());
+    assert!(cert.starts_with(b"-----BEGIN"));
+}
+
+#[test]
+fn obfuscate_bytes_keeps_non_utf8_and_escaped_bytes() {
+    let table = obfuscate_bytes!(b"\x00\xff\x7f\x80\xc3\x28");
+    let empty = obfuscate_bytes!(b"");
+
+    assert_eq!(table, [0x00, 0xff, 0x7f, 0x80, 0xc3, 0x28]);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn obfuscate_bytes_caches_per_call_site() {
+    let first = pinned_cert().as_bytes();
+    let second = pinned_cert().as_bytes();
+
+    assert_eq!(first.as_ptr(), second.as_ptr());
+    assert_eq!(pinned_cert().into_vec(), PINNED_CERT.to_vec());
+}
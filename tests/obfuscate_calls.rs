@@ -0,0 +1,142 @@
+use rust_code_obfuscator::obfuscate_calls;
+
+fn check_license(key: &str) -> bool {
+    key.len() == 8 && key.starts_with("RF-")
+}
+
+fn first_word(text: &str) -> &str {
+    text.split(' ').next().unwrap_or("")
+}
+
+fn widen<T: Into<u64>>(value: T) -> u64 {
+    value.into()
+}
+
+// Eight arguments is the most `#[obfuscate_calls]` reroutes.
+#[allow(clippy::too_many_arguments)]
+fn mix(a: u8, b: u16, c: u32, d: u64, e: i8, f: i16, g: i32, h: i64) -> i128 {
+    a as i128 + b as i128 + c as i128 + d as i128 + e as i128 + f as i128 + g as i128 + h as i128
+}
+
+fn seed() -> u32 {
+    0x5eed
+}
+
+mod keys {
+    pub fn decrypt(data: Vec<u8>, key: u8) -> Vec<u8> {
+        data.into_iter().map(|byte| byte ^ key).collect()
+    }
+}
+
+#[obfuscate_calls(check_license, first_word, widen, mix, seed, keys::decrypt)]
+fn run(input: &str) -> (bool, String, u64, i128, Vec<u8>) {
+    let licensed = check_license(input) && check_license(first_word(input));
+    let word = first_word(input).to_string();
+    let wide = widen(7u8) + widen(seed()) + widen::<u32>(1);
+    let mixed = mix(1, 2, 3, 4, -5, -6, -7, -8);
+    let plain = keys::decrypt(keys::decrypt(b"key".to_vec(), 0x2a), 0x2a);
+    (licensed, word, wide, mixed, plain)
+}
+
+#[test]
+fn obfuscate_calls_preserves_results() {
+    let (licensed, word, wide, mixed, plain) = run("RF-12345 trailing");
+    assert!(!licensed);
+    assert_eq!(word, "RF-12345");
+    assert_eq!(wide, 7 + 0x5eed + 1);
+    assert_eq!(mixed, -16);
+    assert_eq!(plain, b"key");
+
+    assert!(run("RF-12345").0);
+}
+
+struct Vault {
+    secret: Vec<u8>,
+}
+
+#[obfuscate_calls(Vault::unlock, Self::checksum)]
+impl Vault {
+    fn unlock(&self, pin: u32) -> Option<&[u8]> {
+        (Self::checksum(&self.secret) == pin).then_some(&self.secret[..])
+    }
+
+    fn checksum(bytes: &[u8]) -> u32 {
+        bytes.iter().map(|&byte| byte as u32).sum()
+    }
+
+    fn open(&self, pin: u32) -> bool {
+        let attempts: Vec<_> = [pin + 1, pin]
+            .iter()
+            .map(|&p| Vault::unlock(self, p))
+            .collect();
+        attempts.iter().any(Option::is_some)
+    }
+}
+
+#[test]
+fn obfuscate_calls_rewrites_impl_methods() {
+    let vault = Vault {
+        secret: vec![1, 2, 3],
+    };
+    assert_eq!(vault.unlock(6), Some(&[1u8, 2, 3][..]));
+    assert_eq!(vault.unlock(7), None);
+    assert!(vault.open(6));
+    assert!(!vault.open(9));
+}
+
+#[obfuscate_calls(step)]
+mod chain {
+    pub fn step(n: u64) -> u64 {
+        if n & 1 == 0 {
+            n / 2
+        } else {
+            3 * n + 1
+        }
+    }
+
+    pub fn collatz(mut n: u64) -> u32 {
+        let mut count = 0;
+        while n != 1 {
+            n = step(n);
+            count += 1;
+        }
+        count
+    }
+
+    pub async fn delayed(n: u64) -> u64 {
+        step(async { n }.await)
+    }
+}
+
+#[test]
+fn obfuscate_calls_rewrites_modules_and_async_fns() {
+    assert_eq!(chain::collatz(27), 111);
+
+    fn assert_send<T: Send>(_: T) {}
+    assert_send(chain::delayed(5));
+}
+
+mod plain {
+    pub fn check(n: u32) -> u32 {
+        n + 1
+    }
+}
+
+// `extern "C"` fns cannot be rerouted, so these only compile if the calls to
+// them are left alone.
+mod native {
+    pub extern "C" fn check(n: u32) -> u32 {
+        n * 2
+    }
+}
+
+#[obfuscate_calls(plain::check, check)]
+fn same_names(n: u32) -> u32 {
+    let check: extern "C" fn(u32) -> u32 = native::check;
+    plain::check(n) + native::check(n) + check(n)
+}
+
+#[test]
+fn obfuscate_calls_matches_the_exact_path() {
+    assert_eq!(same_names(5), 6 + 10 + 10);
+}
@@ -0,0 +1,36 @@
+use rust_code_obfuscator::{obfuscate_eq, obfuscate_string, ObfStr};
+
+fn admin_user() -> ObfStr {
+    obfuscate_string!("admin")
+}
+
+fn is_admin(user: &str) -> bool {
+    obfuscate_eq!(user, "admin")
+}
+
+#[test]
+fn obfuscate_eq_compares_without_the_plaintext() {
+    assert!(is_admin("admin"));
+    assert!(!is_admin("administrator"));
+    assert!(!is_admin("admi"));
+    assert!(!is_admin(""));
+}
+
+#[test]
+fn obfstr_ct_eq_compares_literals() {
+    let admin = admin_user();
+
+    assert!(admin.ct_eq("admin"));
+    assert!(!admin.ct_eq("admiN"));
+    assert!(!admin.ct_eq("admin\0"));
+    assert!(obfuscate_string!("", pad).ct_eq(""));
+}
+
+#[test]
+fn obfstr_ct_eq_falls_back_for_hand_built_values() {
+    let manual = ObfStr::new(|| "manual");
+
+    assert!(manual.ct_eq("manual"));
+    assert!(!manual.ct_eq("manuaL"));
+    assert!(!manual.ct_eq("manual!"));
+}
@@ -0,0 +1,68 @@
+use rust_code_obfuscator::obfuscate_expr;
+
+macro_rules! check_type {
+    ($($ty:ident),*) => {
+        $(
+            #[test]
+            fn $ty() {
+                let samples: [$ty; 8] = [
+                    0,
+                    1,
+                    2,
+                    <$ty>::MAX,
+                    <$ty>::MIN,
+                    <$ty>::MAX / 3,
+                    <$ty>::MIN.wrapping_add(7),
+                    0x5a as $ty,
+                ];
+                for &a in &samples {
+                    for &b in &samples {
+                        let c = a ^ b.rotate_left(3);
+                        assert_eq!(obfuscate_expr!(a + b), a.wrapping_add(b));
+                        assert_eq!(obfuscate_expr!(a - b), a.wrapping_sub(b));
+                        assert_eq!(obfuscate_expr!(a * b), a.wrapping_mul(b));
+                        assert_eq!(obfuscate_expr!(a & b), a & b);
+                        assert_eq!(obfuscate_expr!(a | b), a | b);
+                        assert_eq!(obfuscate_expr!(a ^ b), a ^ b);
+                        assert_eq!(obfuscate_expr!(!a), !a);
+                        assert_eq!(obfuscate_expr!(-(a ^ 1), depth = 1), (a ^ 1).wrapping_neg());
+                        assert_eq!(
+                            obfuscate_expr!(a + b * c, depth = 3),
+                            a.wrapping_add(b.wrapping_mul(c))
+                        );
+                        assert_eq!(
+                            obfuscate_expr!((a ^ 0x3c) - (b | c) & !a),
+                            (a ^ 0x3c).wrapping_sub(b | c) & !a
+                        );
+                    }
+                }
+            }
+        )*
+    };
+}
+
+check_type!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[test]
+fn obfuscate_expr_keeps_other_operators_and_evaluation_order() {
+    let mut calls = Vec::new();
+    let mut next = |value: u32| {
+        calls.push(value);
+        value
+    };
+
+    let value = obfuscate_expr!(next(7) * next(6) + next(100) / 3 - (next(1) << 4));
+    assert_eq!(value, 7 * 6 + 100 / 3 - (1 << 4));
+    assert_eq!(calls, [7, 6, 100, 1]);
+
+    let n = -128i8;
+    assert_eq!(obfuscate_expr!(n + -128i8), 0);
+    assert_eq!(obfuscate_expr!(3 * 5 + 1), 16);
+}
+
+#[test]
+#[should_panic(expected = "divide by zero")]
+fn obfuscate_expr_keeps_division_checks() {
+    let zero = std::hint::black_box(0u32);
+    let _ = obfuscate_expr!(1 + 10 / zero);
+}
@@ -0,0 +1,201 @@
+// Dispatchers allow the unreachable code they generate themselves.
+#![deny(unreachable_code)]
+
+use rust_code_obfuscator::obfuscate;
+
+#[obfuscate(flatten)]
+fn grid_search(grid: &[Vec<i32>], target: i32) -> Option<(usize, usize)> {
+    let mut visited = 0;
+    for (row, cells) in grid.iter().enumerate() {
+        if cells.is_empty() {
+            continue;
+        }
+        let mut col = 0;
+        while col < cells.len() {
+            visited += 1;
+            if cells[col] < 0 {
+                break;
+            }
+            if cells[col] == target {
+                return Some((row, col));
+            }
+            col += 1;
+        }
+        if visited > 100 {
+            break;
+        }
+    }
+    None
+}
+
+#[obfuscate(flatten)]
+fn parse_sum(input: &str) -> Result<i64, std::num::ParseIntError> {
+    let mut total = 0i64;
+    let mut seen = Vec::new();
+    for part in input.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        seen.push(part.to_string());
+        total += part.parse::<i64>()?;
+        if total > 1_000 {
+            return Ok(-1);
+        }
+    }
+    assert!(seen.len() <= 16);
+    Ok(total)
+}
+
+// Late initialisation is kept outside the dispatchers; this checks it compiles.
+#[allow(clippy::needless_late_init)]
+#[obfuscate(flatten)]
+fn describe(values: Vec<String>, verbose: bool) -> String {
+    let mut out = String::new();
+    let label;
+    if verbose {
+        label = "values";
+    } else {
+        label = "n";
+    }
+    out.push_str(label);
+    out.push('=');
+    if values.is_empty() {
+        out.push_str("none");
+    } else if values.len() == 1 {
+        out.push_str("one");
+    } else {
+        out.push_str("many");
+    }
+    out.push(':');
+    let joined = values.join("|");
+    drop(values);
+    out.push_str(&joined);
+    out
+}
+
+#[obfuscate(flatten)]
+fn countdown(mut n: u32) -> u32 {
+    let mut steps = 0;
+    'outer: loop {
+        loop {
+            if n == 0 {
+                break 'outer;
+            }
+            n -= 1;
+            steps += 1;
+            if n % 3 == 1 {
+                continue 'outer;
+            }
+            if n % 2 == 1 {
+                break;
+            }
+        }
+        steps += 10;
+    }
+    steps
+}
+
+#[obfuscate(flatten)]
+fn must_be_positive(n: i32) -> i32 {
+    if n <= 0 {
+        println!("rejecting {n}");
+        eprintln!("rejecting {}", n);
+        panic!("not positive: {n}");
+    }
+    println!("accepted");
+    n
+}
+
+// The trailing `return` is the diverging last statement under test.
+#[allow(clippy::needless_return)]
+#[obfuscate(flatten)]
+fn bail(n: i32) -> i32 {
+    println!("bailing with {n}");
+    println!("bailing now");
+    return n * 2;
+}
+
+struct Counter {
+    hits: Vec<u32>,
+}
+
+#[obfuscate(flatten)]
+impl Counter {
+    fn record(&mut self, value: u32) -> usize {
+        if value == 0 {
+            return self.hits.len();
+        }
+        self.hits.push(value);
+        self.hits.sort();
+        self.hits.dedup();
+        self.hits.len()
+    }
+
+    fn into_total(self) -> u32 {
+        let total = self.hits.iter().sum();
+        println!("total {total}");
+        println!("entries {}", self.hits.len());
+        total
+    }
+}
+
+#[test]
+fn flatten_keeps_early_returns_and_loop_control() {
+    let grid = vec![vec![], vec![1, 2, 3], vec![4, -1, 9], vec![7, 8, 9]];
+
+    assert_eq!(grid_search(&grid, 3), Some((1, 2)));
+    assert_eq!(grid_search(&grid, 8), Some((3, 1)));
+    assert_eq!(grid_search(&grid, 9), Some((3, 2)));
+    assert_eq!(grid_search(&grid, 42), None);
+
+    let mut expected = 0;
+    let mut n = 20u32;
+    'outer: loop {
+        loop {
+            if n == 0 {
+                break 'outer;
+            }
+            n -= 1;
+            expected += 1;
+            if n % 3 == 1 {
+                continue 'outer;
+            }
+            if n % 2 == 1 {
+                break;
+            }
+        }
+        expected += 10;
+    }
+    assert_eq!(countdown(20), expected);
+}
+
+#[test]
+fn flatten_keeps_question_mark_and_moves() {
+    assert_eq!(parse_sum("1, 2, ,3"), Ok(6));
+    assert_eq!(parse_sum("999,2"), Ok(-1));
+    assert!(parse_sum("1,x").is_err());
+
+    let values = vec!["a".to_string(), "b".to_string()];
+    assert_eq!(describe(values, true), "values=many:a|b");
+    assert_eq!(describe(vec!["z".to_string()], false), "n=one:z");
+    assert_eq!(describe(Vec::new(), false), "n=none:");
+}
+
+#[test]
+fn flatten_keeps_diverging_statements() {
+    assert_eq!(must_be_positive(4), 4);
+    assert!(std::panic::catch_unwind(|| must_be_positive(-1)).is_err());
+    assert_eq!(bail(21), 42);
+}
+
+#[test]
+fn flatten_rewrites_impl_methods() {
+    let mut counter = Counter { hits: Vec::new() };
+
+    assert_eq!(counter.record(3), 1);
+    assert_eq!(counter.record(3), 1);
+    assert_eq!(counter.record(1), 2);
+    assert_eq!(counter.record(0), 2);
+    assert_eq!(counter.into_total(), 4);
+}
@@ -0,0 +1,30 @@
+use rust_code_obfuscator::{obfuscate_include_bytes, obfuscate_include_str, ObfStr};
+
+fn license() -> ObfStr {
+    obfuscate_include_str!("LICENSE")
+}
+
+#[test]
+fn obfuscate_include_str_matches_include_str() {
+    assert_eq!(license(), include_str!("../LICENSE"));
+    assert_eq!(license().as_str().as_ptr(), license().as_str().as_ptr());
+}
+
+#[test]
+fn obfuscate_include_str_decrypts_files_spanning_several_chunks() {
+    let guide = obfuscate_include_str!("obfuscation_fundamentals.html");
+
+    assert!(guide.len() > 16 * 1024);
+    assert_eq!(guide, include_str!("../obfuscation_fundamentals.html"));
+}
+
+#[test]
+fn obfuscate_include_bytes_keeps_binary_contents() {
+    let blob = obfuscate_include_bytes!("tests/fixtures/blob.bin");
+
+    assert_eq!(blob, include_bytes!("fixtures/blob.bin"));
+    assert_eq!(
+        blob,
+        [0x00, 0x01, 0xfe, 0xff, b'b', b'i', b'n', b'a', b'r', b'y', 0x80]
+    );
+}
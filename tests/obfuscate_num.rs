@@ -12,3 +12,16 @@ fn obfuscate_num_round_trips_from_public_crate() {
     assert_eq!(inferred, 1234);
     assert_eq!(inferred_from_context, 1234u64);
 }
+
+#[test]
+fn obfuscate_num_accepts_float_and_char_literals() {
+    let price: f64 = obfuscate_num!(0.875f64);
+    let threshold: f32 = obfuscate_num!(0.25);
+    let negative_zero = obfuscate_num!(-0.0f32);
+    let separator = obfuscate_num!('x');
+
+    assert_eq!(price, 0.875);
+    assert_eq!(threshold, 0.25);
+    assert!(negative_zero == 0.0 && negative_zero.is_sign_negative());
+    assert_eq!(separator, 'x');
+}
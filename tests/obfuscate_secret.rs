@@ -0,0 +1,40 @@
+use rust_code_obfuscator::{obfuscate_secret, SecretStr};
+
+fn api_token() -> SecretStr {
+    obfuscate_secret!("sk_live_51H8")
+}
+
+#[test]
+fn obfuscate_secret_derefs_to_the_plaintext() {
+    let token = api_token();
+
+    assert_eq!(token, "sk_live_51H8");
+    assert_eq!(token.as_str(), "sk_live_51H8");
+    assert!(token.starts_with("sk_live"));
+}
+
+#[test]
+fn obfuscate_secret_decrypts_a_fresh_buffer_per_call() {
+    let first = api_token();
+    let second = api_token();
+
+    assert_eq!(first.as_str(), second.as_str());
+    assert_ne!(first.as_ptr(), second.as_ptr());
+}
+
+#[test]
+fn obfuscate_secret_redacts_debug_output_and_supports_padding() {
+    let padded = obfuscate_secret!("pin", pad = 32);
+
+    assert_eq!(format!("{padded:?}"), "SecretStr(<redacted>)");
+    assert_eq!(padded, "pin");
+}
+
+#[test]
+fn obfuscate_secret_opens_padded_secrets() {
+    let bucketed = obfuscate_secret!("pässwört-🔑", pad);
+    let exact = obfuscate_secret!("0123456789abcdef", pad = 16);
+
+    assert_eq!(bucketed.as_str(), "pässwört-🔑");
+    assert_eq!(exact.as_str(), "0123456789abcdef");
+}
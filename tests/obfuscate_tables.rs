@@ -0,0 +1,46 @@
+use rust_code_obfuscator::obfuscator::ObfMap;
+use rust_code_obfuscator::{obfuscate_array, obfuscate_map, obfuscate_string, ObfStr};
+
+#[derive(Debug, PartialEq)]
+enum Role {
+    Admin,
+    Operator,
+}
+
+static ROLES: ObfMap<Role, 2> = obfuscate_map! {
+    "admin" => Role::Admin,
+    "ops" => Role::Operator,
+};
+
+fn blocked_users() -> &'static [&'static str] {
+    obfuscate_array!["root", "guest", "nobody"]
+}
+
+#[test]
+fn obfuscate_array_returns_a_cached_static_slice() {
+    assert_eq!(blocked_users(), ["root", "guest", "nobody"]);
+    assert!(blocked_users().contains(&"guest"));
+    assert_eq!(blocked_users().as_ptr(), blocked_users().as_ptr());
+}
+
+#[test]
+fn obfuscate_map_works_in_statics() {
+    assert_eq!(ROLES.get("admin"), Some(&Role::Admin));
+    assert_eq!(ROLES.get("ops"), Some(&Role::Operator));
+    assert_eq!(ROLES.get("root"), None);
+}
+
+#[test]
+fn obfuscate_map_values_can_be_obfuscated_strings() {
+    let endpoints = obfuscate_map! {
+        "prod" => obfuscate_string!("https://api.example.com"),
+        "staging" => obfuscate_string!("https://staging.example.com"),
+    };
+
+    let prod: Option<&ObfStr> = endpoints.get("prod");
+    assert_eq!(
+        prod.map(|url| url.as_str()),
+        Some("https://api.example.com")
+    );
+    assert!(endpoints.get("dev").is_none());
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "registry")]
+
+use rust_code_obfuscator::{
+    obfuscate_array, obfuscate_bytes, obfuscate_include_bytes, obfuscate_string, prewarm,
+    purge_cache, ObfBytes, ObfStr,
+};
+
+fn greeting() -> ObfStr {
+    obfuscate_string!("registered greeting")
+}
+
+fn key_id() -> ObfBytes {
+    obfuscate_bytes!(b"\x01\x02\x03")
+}
+
+fn reserved() -> &'static [&'static str] {
+    obfuscate_array!["root", "admin"]
+}
+
+fn blob() -> ObfBytes {
+    obfuscate_include_bytes!("tests/fixtures/blob.bin")
+}
+
+// One test on purpose: `purge_cache` invalidates every cached reference in
+// the binary, so nothing else may run concurrently.
+#[test]
+fn registry_prewarms_lists_and_purges_call_sites() {
+    prewarm();
+
+    // Call sites are only listed in builds with `debug_assertions`.
+    #[cfg(debug_assertions)]
+    {
+        let sites: Vec<_> = rust_code_obfuscator::registry::call_sites()
+            .filter(|site| site.file.ends_with("registry.rs"))
+            .collect();
+        assert_eq!(sites.len(), 4);
+        assert!(sites.iter().any(|site| site.len == 19));
+        assert!(sites.iter().any(|site| site.len == 3));
+        assert!(sites.iter().any(|site| site.len == 9));
+        assert!(sites
+            .iter()
+            .any(|site| site.len == std::fs::read("tests/fixtures/blob.bin").unwrap().len()));
+    }
+
+    assert_eq!(greeting(), "registered greeting");
+    assert_eq!(key_id(), [1, 2, 3]);
+    assert_eq!(reserved(), ["root", "admin"]);
+    let blob_len = blob().len();
+
+    unsafe { purge_cache() };
+
+    assert_eq!(greeting(), "registered greeting");
+    assert_eq!(key_id(), [1, 2, 3]);
+    assert_eq!(reserved(), ["root", "admin"]);
+    assert_eq!(blob().len(), blob_len);
+}
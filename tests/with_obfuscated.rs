@@ -0,0 +1,47 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use rust_code_obfuscator::{obfuscate_string, with_obfuscated, ObfStr};
+
+fn db_password() -> ObfStr {
+    obfuscate_string!("hunter2-db")
+}
+
+#[test]
+fn with_obfuscated_lends_the_plaintext_to_the_closure() {
+    let mut seen = String::new();
+    let len = with_obfuscated!("hunter2-db", |s: &str| {
+        seen.push_str(s);
+        s.len()
+    });
+
+    assert_eq!(len, 10);
+    assert_eq!(seen, "hunter2-db");
+}
+
+#[test]
+fn with_obfuscated_survives_a_panicking_closure() {
+    let result = catch_unwind(|| with_obfuscated!("boom", |_: &str| panic!("closure failed")));
+
+    assert!(result.is_err());
+    assert_eq!(with_obfuscated!("boom", str::to_owned), "boom");
+}
+
+#[test]
+fn obfstr_with_decrypts_outside_the_cache() {
+    let password = db_password();
+
+    assert!(password.with(|s| s == "hunter2-db"));
+    assert_eq!(password.with(str::len), 10);
+
+    let result = catch_unwind(AssertUnwindSafe(|| password.with(|_| panic!("closure failed"))));
+    assert!(result.is_err());
+    assert_eq!(password, "hunter2-db");
+}
+
+#[test]
+fn obfstr_with_strips_padding() {
+    let padded = obfuscate_string!("hunter2-db", pad = 64);
+
+    assert_eq!(padded.with(str::to_owned), "hunter2-db");
+    assert_eq!(padded.as_str(), "hunter2-db");
+}